@@ -0,0 +1,5 @@
+-- Drop the unique email index
+DROP INDEX customers_email_unique ON customers;
+
+-- Drop the normalised email column
+ALTER TABLE customers DROP COLUMN email_normalized;
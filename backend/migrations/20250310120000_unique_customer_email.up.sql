@@ -0,0 +1,38 @@
+-- Refuse to run while customers already share an email (case-insensitive):
+-- which record survives is for an operator to decide, so the error lists the
+-- conflicting emails instead of this migration deleting anyone. Merge them
+-- first (see "Duplicate customer emails" in the README).
+DROP PROCEDURE IF EXISTS check_unique_customer_emails;
+
+CREATE PROCEDURE check_unique_customer_emails()
+BEGIN
+    DECLARE shared INT;
+    DECLARE emails TEXT;
+    DECLARE detail VARCHAR(128);
+
+    SELECT COUNT(*), GROUP_CONCAT(email_key ORDER BY email_key SEPARATOR ', ')
+    INTO shared, emails
+    FROM (
+        SELECT LOWER(email) AS email_key
+        FROM customers
+        GROUP BY LOWER(email)
+        HAVING COUNT(*) > 1
+    ) duplicates;
+
+    IF shared > 0 THEN
+        -- MESSAGE_TEXT holds at most 128 characters
+        SET detail = LEFT(CONCAT(shared, ' email(s) used by several customers, merge them first: ', emails), 128);
+        SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = detail;
+    END IF;
+END;
+
+CALL check_unique_customer_emails();
+DROP PROCEDURE check_unique_customer_emails;
+
+-- Normalised, lower-cased copy of the email used to enforce uniqueness
+-- regardless of the column collation.
+ALTER TABLE customers
+    ADD COLUMN email_normalized VARCHAR(255) AS (LOWER(email)) STORED;
+
+-- Enforce one customer per email address (case-insensitive)
+CREATE UNIQUE INDEX customers_email_unique ON customers (email_normalized);
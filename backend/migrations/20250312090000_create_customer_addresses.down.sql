@@ -0,0 +1,13 @@
+-- Drop the order address snapshot columns
+ALTER TABLE orders
+    DROP COLUMN shipping_street,
+    DROP COLUMN shipping_city,
+    DROP COLUMN shipping_postal_code,
+    DROP COLUMN shipping_country_code,
+    DROP COLUMN billing_street,
+    DROP COLUMN billing_city,
+    DROP COLUMN billing_postal_code,
+    DROP COLUMN billing_country_code;
+
+-- Drop the `customer_addresses` table
+DROP TABLE customer_addresses;
@@ -0,0 +1,24 @@
+-- Create the `customer_addresses` table
+CREATE TABLE customer_addresses (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    customer_id INT NOT NULL,
+    address_type VARCHAR(16) NOT NULL,
+    street VARCHAR(255) NOT NULL,
+    city VARCHAR(255) NOT NULL,
+    postal_code VARCHAR(20) NOT NULL,
+    country_code CHAR(2) NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    CHECK (address_type IN ('billing', 'shipping')),
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+-- Snapshot of the shipping and billing address at order time
+ALTER TABLE orders
+    ADD COLUMN shipping_street VARCHAR(255),
+    ADD COLUMN shipping_city VARCHAR(255),
+    ADD COLUMN shipping_postal_code VARCHAR(20),
+    ADD COLUMN shipping_country_code CHAR(2),
+    ADD COLUMN billing_street VARCHAR(255),
+    ADD COLUMN billing_city VARCHAR(255),
+    ADD COLUMN billing_postal_code VARCHAR(20),
+    ADD COLUMN billing_country_code CHAR(2);
@@ -0,0 +1,5 @@
+-- Drop the row version columns
+ALTER TABLE customer_addresses DROP COLUMN version;
+ALTER TABLE orders DROP COLUMN version;
+ALTER TABLE customers DROP COLUMN version;
+ALTER TABLE products DROP COLUMN version;
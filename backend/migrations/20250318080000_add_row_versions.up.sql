@@ -0,0 +1,5 @@
+-- Row versions for optimistic concurrency; bumped on every write and exposed as the ETag
+ALTER TABLE products ADD COLUMN version INT NOT NULL DEFAULT 1;
+ALTER TABLE customers ADD COLUMN version INT NOT NULL DEFAULT 1;
+ALTER TABLE orders ADD COLUMN version INT NOT NULL DEFAULT 1;
+ALTER TABLE customer_addresses ADD COLUMN version INT NOT NULL DEFAULT 1;
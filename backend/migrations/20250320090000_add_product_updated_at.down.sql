@@ -0,0 +1,2 @@
+-- Drop the product modification timestamp
+ALTER TABLE products DROP COLUMN updated_at;
@@ -0,0 +1,3 @@
+-- Last change of each product, sent as `Last-Modified` on catalogue reads
+ALTER TABLE products
+    ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP;
@@ -0,0 +1,2 @@
+-- Drop stored idempotent responses
+DROP TABLE idempotency_keys;
@@ -0,0 +1,12 @@
+-- Responses to POST requests sent with an Idempotency-Key, replayed when the client retries
+CREATE TABLE idempotency_keys (
+    idempotency_key VARCHAR(255) NOT NULL PRIMARY KEY,
+    -- SHA-256 of method, path and body, to catch a key reused for a different request
+    request_hash CHAR(64) NOT NULL,
+    -- NULL while the first request is still being handled
+    response_status SMALLINT UNSIGNED NULL,
+    response_headers JSON NULL,
+    response_body MEDIUMBLOB NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_idempotency_keys_created_at (created_at)
+);
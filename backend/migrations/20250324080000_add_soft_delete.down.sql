@@ -0,0 +1,4 @@
+-- Drop the trash markers; trashed rows become live again
+ALTER TABLE orders DROP INDEX idx_orders_deleted_at, DROP COLUMN deleted_at;
+ALTER TABLE customers DROP INDEX idx_customers_deleted_at, DROP COLUMN deleted_at;
+ALTER TABLE products DROP INDEX idx_products_deleted_at, DROP COLUMN deleted_at;
@@ -0,0 +1,4 @@
+-- Soft delete: rows with deleted_at set are in the trash until restored or purged
+ALTER TABLE products ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL, ADD INDEX idx_products_deleted_at (deleted_at);
+ALTER TABLE customers ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL, ADD INDEX idx_customers_deleted_at (deleted_at);
+ALTER TABLE orders ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL, ADD INDEX idx_orders_deleted_at (deleted_at);
@@ -0,0 +1,2 @@
+-- Drop the audit log
+DROP TABLE audit_log;
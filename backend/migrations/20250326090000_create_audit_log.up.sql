@@ -0,0 +1,18 @@
+-- One row per record written through the API, with its state before and after the write
+CREATE TABLE audit_log (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    -- `X-Actor` of the request, `anonymous` without one, `system` for background jobs
+    actor VARCHAR(255) NOT NULL,
+    action VARCHAR(16) NOT NULL,
+    -- Table of the record, e.g. `orders`
+    resource VARCHAR(64) NOT NULL,
+    resource_id INT NOT NULL,
+    -- Response-shaped snapshots; NULL before a create and after a purge
+    before_data JSON NULL,
+    after_data JSON NULL,
+    request_id VARCHAR(128) NULL,
+    created_at TIMESTAMP(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_audit_log_resource (resource, resource_id, created_at),
+    INDEX idx_audit_log_actor (actor, created_at),
+    INDEX idx_audit_log_created_at (created_at)
+);
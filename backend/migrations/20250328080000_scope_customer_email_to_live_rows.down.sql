@@ -0,0 +1,4 @@
+-- Count trashed customers' emails again; fails while a trashed and a live
+-- customer share an address
+ALTER TABLE customers
+    MODIFY COLUMN email_normalized VARCHAR(255) AS (LOWER(email)) STORED;
@@ -0,0 +1,6 @@
+-- Only live customers take part in email uniqueness: the normalised copy is
+-- NULL for trashed rows, and the unique index ignores NULLs, so a trashed
+-- customer's email can be reused. Restoring it answers 409 if it was.
+ALTER TABLE customers
+    MODIFY COLUMN email_normalized VARCHAR(255)
+        AS (IF(deleted_at IS NULL, LOWER(email), NULL)) STORED;
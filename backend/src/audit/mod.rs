@@ -0,0 +1,210 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use sqlx::{mysql::MySqlRow, types::Json, FromRow, MySqlConnection, MySqlPool};
+use crate::dto::v1::{AddressDto, CustomerDto, FieldChange, HistoryEntryDto, OrderDto, ProductDto};
+use crate::metrics::TimedQuery;
+use crate::middleware::{actor::current_actor, request_id::current_request_id};
+use crate::models::{address::CustomerAddress, audit::AuditEntry, customer::Customer, order::Order, product::Product};
+use crate::utils::AppError;
+
+// Actor recorded for writes made outside a request, such as the trash purge
+const SYSTEM_ACTOR: &str = "system";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+/// The record as the API returns it (the v1 shape), or `None` when it does not exist
+pub async fn snapshot(conn: &mut MySqlConnection, table: &'static str, id: i32) -> Result<Option<Value>, AppError> {
+    match table {
+        "products" => load::<Product, ProductDto>(conn, table, id).await,
+        "customers" => load::<Customer, CustomerDto>(conn, table, id).await,
+        "orders" => load::<Order, OrderDto>(conn, table, id).await,
+        "customer_addresses" => load::<CustomerAddress, AddressDto>(conn, table, id).await,
+        _ => Ok(None),
+    }
+}
+
+/// Snapshots of several records, to be passed to `record_all` after writing them
+pub async fn snapshots(conn: &mut MySqlConnection, table: &'static str, ids: &[i32]) -> Result<Vec<(i32, Option<Value>)>, AppError> {
+    let mut snapshots = Vec::with_capacity(ids.len());
+    for &id in ids {
+        snapshots.push((id, snapshot(&mut *conn, table, id).await?));
+    }
+    Ok(snapshots)
+}
+
+/// Log a write inside the caller's transaction: `before` was taken ahead of it, the state
+/// after it is read now
+pub async fn record(conn: &mut MySqlConnection, action: AuditAction, table: &'static str, id: i32, before: Option<Value>) -> Result<(), AppError> {
+    let after = snapshot(&mut *conn, table, id).await?;
+
+    sqlx::query(
+        "INSERT INTO audit_log (actor, action, resource, resource_id, before_data, after_data, request_id) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(current_actor().unwrap_or_else(|| SYSTEM_ACTOR.to_string()))
+    .bind(action.as_str())
+    .bind(table)
+    .bind(id)
+    .bind(before.map(Json))
+    .bind(after.map(Json))
+    .bind(current_request_id())
+    .execute(conn)
+    .timed("audit_record")
+    .await
+    .map_err(AppError::DatabaseError)?;
+    Ok(())
+}
+
+/// `record` for every record captured by `snapshots`
+pub async fn record_all(conn: &mut MySqlConnection, action: AuditAction, table: &'static str, snapshots: Vec<(i32, Option<Value>)>) -> Result<(), AppError> {
+    for (id, before) in snapshots {
+        record(&mut *conn, action, table, id, before).await?;
+    }
+    Ok(())
+}
+
+/// Scrub a customer's personal data from the audit log when they are anonymised: their own
+/// and their addresses' snapshots are dropped and the streets cleared from their orders'
+pub async fn redact_customer(conn: &mut MySqlConnection, customer_id: i32) -> Result<(), AppError> {
+    let statements = [
+        "UPDATE audit_log SET before_data = NULL, after_data = NULL WHERE resource = 'customers' AND resource_id = ?",
+        "UPDATE audit_log SET before_data = NULL, after_data = NULL WHERE resource = 'customer_addresses' \
+         AND ? IN (JSON_EXTRACT(before_data, '$.customer_id'), JSON_EXTRACT(after_data, '$.customer_id'))",
+        "UPDATE audit_log SET \
+         before_data = JSON_REPLACE(before_data, '$.shipping_street', NULL, '$.shipping_postal_code', NULL, '$.billing_street', NULL, '$.billing_postal_code', NULL), \
+         after_data = JSON_REPLACE(after_data, '$.shipping_street', NULL, '$.shipping_postal_code', NULL, '$.billing_street', NULL, '$.billing_postal_code', NULL) \
+         WHERE resource = 'orders' AND ? IN (JSON_EXTRACT(before_data, '$.customer_id'), JSON_EXTRACT(after_data, '$.customer_id'))",
+    ];
+    for statement in statements {
+        sqlx::query(statement)
+            .bind(customer_id)
+            .execute(&mut *conn)
+            .timed("audit_redact")
+            .await
+            .map_err(AppError::DatabaseError)?;
+    }
+    Ok(())
+}
+
+/// A record's audit entries, oldest first, each reduced to the fields it changed
+pub async fn history(pool: &MySqlPool, table: &'static str, id: i32) -> Result<Vec<HistoryEntryDto>, AppError> {
+    let entries = sqlx::query_as::<_, AuditEntry>("SELECT * FROM audit_log WHERE resource = ? AND resource_id = ? ORDER BY id")
+        .bind(table)
+        .bind(id)
+        .fetch_all(pool)
+        .timed("record_history")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| HistoryEntryDto {
+            changes: field_changes(entry.before_data.as_ref().map(|data| &data.0), entry.after_data.as_ref().map(|data| &data.0)),
+            id: entry.id,
+            at: entry.created_at,
+            actor: entry.actor,
+            action: entry.action,
+            request_id: entry.request_id,
+        })
+        .collect())
+}
+
+// Fields that differ between two snapshots, by field name; the row version changes on every
+// write and is left out
+fn field_changes(before: Option<&Value>, after: Option<&Value>) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+
+    fields
+        .into_iter()
+        .filter(|field| *field != "version")
+        .filter_map(|field| {
+            let from = before.get(field).cloned().unwrap_or(Value::Null);
+            let to = after.get(field).cloned().unwrap_or(Value::Null);
+            (from != to).then(|| FieldChange { field: field.clone(), from, to })
+        })
+        .collect()
+}
+
+async fn load<T, D>(conn: &mut MySqlConnection, table: &'static str, id: i32) -> Result<Option<Value>, AppError>
+where
+    T: for<'r> FromRow<'r, MySqlRow> + Send + Unpin,
+    D: From<T> + Serialize,
+{
+    let row = sqlx::query_as::<_, T>(&format!("SELECT * FROM {} WHERE id = ?", table))
+        .bind(id)
+        .fetch_optional(conn)
+        .timed("audit_snapshot")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    Ok(row.map(|row| serde_json::to_value(D::from(row)).unwrap_or(Value::Null)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn changes(before: Option<Value>, after: Option<Value>) -> Vec<(String, Value, Value)> {
+        field_changes(before.as_ref(), after.as_ref())
+            .into_iter()
+            .map(|change| (change.field, change.from, change.to))
+            .collect()
+    }
+
+    #[test]
+    fn only_changed_fields_are_listed() {
+        let before = json!({ "id": 4, "quantity": 2, "order_date": "2025-03-01", "version": 1 });
+        let after = json!({ "id": 4, "quantity": 5, "order_date": "2025-03-01", "version": 2 });
+        assert_eq!(changes(Some(before), Some(after)), vec![("quantity".to_string(), json!(2), json!(5))]);
+    }
+
+    #[test]
+    fn creates_and_purges_change_every_field_from_or_to_null() {
+        let product = json!({ "id": 1, "name": "Rye bread", "version": 1 });
+        assert_eq!(
+            changes(None, Some(product.clone())),
+            vec![("id".to_string(), Value::Null, json!(1)), ("name".to_string(), Value::Null, json!("Rye bread"))]
+        );
+        assert_eq!(
+            changes(Some(product), None),
+            vec![("id".to_string(), json!(1), Value::Null), ("name".to_string(), json!("Rye bread"), Value::Null)]
+        );
+    }
+
+    #[test]
+    fn fields_present_on_one_side_only_are_compared_with_null() {
+        let before = json!({ "description": null, "deleted_at": null });
+        let after = json!({ "deleted_at": "2025-03-24T08:00:00Z", "shipping_address": { "city": "Bratislava" } });
+        assert_eq!(
+            changes(Some(before), Some(after)),
+            vec![
+                ("deleted_at".to_string(), Value::Null, json!("2025-03-24T08:00:00Z")),
+                ("shipping_address".to_string(), Value::Null, json!({ "city": "Bratislava" })),
+            ]
+        );
+        assert!(changes(None, None).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use axum::http::HeaderValue;
+use metrics::counter;
+use sqlx::{Error as SqlxError, MySqlPool};
+use std::sync::{atomic::{AtomicU64, Ordering}, Arc, RwLock};
+use std::time::{Duration, Instant};
+use crate::config::CacheConfig;
+use crate::metrics::TimedQuery;
+use crate::models::product::Product;
+
+// Product reads: the `Cache-Control` policy sent to clients plus an optional in-process
+// snapshot of the catalogue, dropped whenever a product is written through this instance
+#[derive(Clone)]
+pub struct ProductCache {
+    cache_control: HeaderValue,
+    snapshot: Option<Arc<Snapshot>>,
+}
+
+struct Snapshot {
+    ttl: Duration,
+    // Bumped by every invalidation, so a load racing a write never stores the rows it read
+    generation: AtomicU64,
+    entry: RwLock<Option<Entry>>,
+}
+
+struct Entry {
+    products: Arc<Vec<Product>>,
+    generation: u64,
+    loaded_at: Instant,
+}
+
+impl ProductCache {
+    pub fn new(config: &CacheConfig, enabled: bool) -> Self {
+        let cache_control = match config.products_max_age_secs {
+            0 => HeaderValue::from_static("no-cache"),
+            secs => HeaderValue::from_str(&format!("public, max-age={}", secs))
+                .unwrap_or(HeaderValue::from_static("no-cache")),
+        };
+        let snapshot = enabled.then(|| Arc::new(Snapshot {
+            ttl: Duration::from_secs(config.products_ttl_secs),
+            generation: AtomicU64::new(0),
+            entry: RwLock::new(None),
+        }));
+        Self { cache_control, snapshot }
+    }
+
+    // `Cache-Control` value for product responses
+    pub fn cache_control(&self) -> HeaderValue {
+        self.cache_control.clone()
+    }
+
+    // All products, from the snapshot while it is fresh
+    pub async fn products(&self, pool: &MySqlPool) -> Result<Arc<Vec<Product>>, SqlxError> {
+        let Some(snapshot) = &self.snapshot else {
+            return load_products(pool).await.map(Arc::new);
+        };
+
+        let generation = snapshot.generation.load(Ordering::Acquire);
+        if let Some(entry) = snapshot.entry.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            if entry.generation == generation && entry.loaded_at.elapsed() < snapshot.ttl {
+                counter!("product_cache_requests_total", "result" => "hit").increment(1);
+                return Ok(entry.products.clone());
+            }
+        }
+
+        counter!("product_cache_requests_total", "result" => "miss").increment(1);
+        let products = Arc::new(load_products(pool).await?);
+
+        let mut entry = snapshot.entry.write().unwrap_or_else(|e| e.into_inner());
+        if snapshot.generation.load(Ordering::Acquire) == generation {
+            *entry = Some(Entry { products: products.clone(), generation, loaded_at: Instant::now() });
+        }
+        Ok(products)
+    }
+
+    // A single product; served from the snapshot when caching is on
+    pub async fn product(&self, pool: &MySqlPool, id: i32) -> Result<Option<Product>, SqlxError> {
+        if self.snapshot.is_none() {
+            return sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ? AND deleted_at IS NULL")
+                .bind(id)
+                .fetch_optional(pool)
+                .timed("get_product")
+                .await;
+        }
+        Ok(self.products(pool).await?.iter().find(|product| product.id == id).cloned())
+    }
+
+    // Drop the snapshot after a product write
+    pub fn invalidate(&self) {
+        if let Some(snapshot) = &self.snapshot {
+            snapshot.generation.fetch_add(1, Ordering::AcqRel);
+            *snapshot.entry.write().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+}
+
+// Weak entity tag for the whole catalogue, derived from every product's id and version
+// (FNV-1a, so all instances agree on it)
+pub fn catalogue_etag(products: &[Product]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for product in products {
+        for byte in product.id.to_be_bytes().into_iter().chain(product.version.to_be_bytes()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("W/\"{}-{:x}\"", products.len(), hash)
+}
+
+async fn load_products(pool: &MySqlPool) -> Result<Vec<Product>, SqlxError> {
+    sqlx::query_as::<_, Product>("SELECT * FROM products WHERE deleted_at IS NULL ORDER BY id")
+        .fetch_all(pool)
+        .timed("list_products")
+        .await
+}
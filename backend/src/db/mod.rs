@@ -0,0 +1,65 @@
+use sqlx::mysql::{MySqlConnection, MySqlPool, MySqlPoolOptions};
+use sqlx::Connection;
+use sqlx::migrate::Migrator;
+use std::time::Duration;
+use tracing::{info, warn, error};
+use crate::config::DatabaseConfig;
+
+/// Migrations embedded from `./migrations`
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Build the connection pool, retrying with exponential backoff while the database is not reachable yet
+pub async fn connect_with_retry(config: &DatabaseConfig, url: &str) -> Result<MySqlPool, sqlx::Error> {
+    let idle_timeout = match config.idle_timeout_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    let max_backoff = Duration::from_secs(config.connect_max_backoff_secs);
+    let mut backoff = Duration::from_millis(config.connect_initial_backoff_ms);
+
+    for attempt in 1..=config.connect_attempts {
+        info!(attempt, max_attempts = config.connect_attempts, "Connecting to the database");
+
+        // Probe with a single connection first: the pool itself would keep retrying
+        // silently until `acquire_timeout` elapses
+        match MySqlConnection::connect(url).await {
+            Ok(connection) => {
+                let _ = connection.close().await;
+                let pool = MySqlPoolOptions::new()
+                    .max_connections(config.max_connections)
+                    .min_connections(config.min_connections)
+                    .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+                    .idle_timeout(idle_timeout)
+                    .connect(url)
+                    .await?;
+
+                info!(attempt, "Connected to the database");
+                return Ok(pool);
+            }
+            Err(e) if attempt == config.connect_attempts => {
+                error!(attempt, error = ?e, "Giving up connecting to the database");
+                return Err(e);
+            }
+            Err(e) => {
+                warn!(attempt, retry_in_ms = backoff.as_millis() as u64, error = ?e, "Database not reachable yet");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+            }
+        }
+    }
+
+    // `connect_attempts` is validated to be at least 1, so the loop always returns
+    Err(sqlx::Error::PoolClosed)
+}
+
+/// Whether every embedded migration has been applied successfully
+pub async fn migrations_applied(pool: &MySqlPool) -> Result<bool, sqlx::Error> {
+    let applied = sqlx::query_scalar::<_, i64>("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .all(|migration| applied.contains(&migration.version)))
+}
@@ -0,0 +1,4 @@
+// Response shapes per API version, decoupled from the database rows in `models`
+pub mod v1;
+// v2 reuses the v1 shapes for everything except orders
+pub mod v2;
@@ -0,0 +1,212 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+use crate::models::{address::CustomerAddress, audit::AuditEntry, customer::Customer, order::Order, product::Product};
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ProductDto {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    #[schema(example = "4.99")]
+    pub price: Decimal,
+    pub in_stock: bool,
+    /// Row version, also sent as the `ETag`
+    pub version: i32,
+    /// When the record was moved to the trash, if it was
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<Product> for ProductDto {
+    fn from(product: Product) -> Self {
+        ProductDto {
+            id: product.id,
+            name: product.name,
+            description: product.description,
+            price: product.price,
+            in_stock: product.in_stock,
+            version: product.version,
+            deleted_at: product.deleted_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CustomerDto {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub address: String,
+    /// Row version, also sent as the `ETag`
+    pub version: i32,
+    /// When the record was moved to the trash, if it was
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<Customer> for CustomerDto {
+    fn from(customer: Customer) -> Self {
+        CustomerDto {
+            id: customer.id,
+            name: customer.name,
+            email: customer.email,
+            address: customer.address,
+            version: customer.version,
+            deleted_at: customer.deleted_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct AddressDto {
+    pub id: i32,
+    pub customer_id: i32,
+    pub address_type: String,
+    pub street: String,
+    pub city: String,
+    pub postal_code: String,
+    pub country_code: String,
+    pub is_default: bool,
+    /// Row version, also sent as the `ETag`
+    pub version: i32,
+}
+
+impl From<CustomerAddress> for AddressDto {
+    fn from(address: CustomerAddress) -> Self {
+        AddressDto {
+            id: address.id,
+            customer_id: address.customer_id,
+            address_type: address.address_type,
+            street: address.street,
+            city: address.city,
+            postal_code: address.postal_code,
+            country_code: address.country_code,
+            is_default: address.is_default,
+            version: address.version,
+        }
+    }
+}
+
+// The address snapshot is flattened into prefixed columns, as stored
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OrderDto {
+    pub id: i32,
+    pub customer_id: i32,
+    pub product_id: i32,
+    pub quantity: i32,
+    pub order_date: NaiveDate,
+    pub shipping_street: Option<String>,
+    pub shipping_city: Option<String>,
+    pub shipping_postal_code: Option<String>,
+    pub shipping_country_code: Option<String>,
+    pub billing_street: Option<String>,
+    pub billing_city: Option<String>,
+    pub billing_postal_code: Option<String>,
+    pub billing_country_code: Option<String>,
+    /// Row version, also sent as the `ETag`
+    pub version: i32,
+    /// When the record was moved to the trash, if it was
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<Order> for OrderDto {
+    fn from(order: Order) -> Self {
+        OrderDto {
+            id: order.id,
+            customer_id: order.customer_id,
+            product_id: order.product_id,
+            quantity: order.quantity,
+            order_date: order.order_date,
+            shipping_street: order.shipping_street,
+            shipping_city: order.shipping_city,
+            shipping_postal_code: order.shipping_postal_code,
+            shipping_country_code: order.shipping_country_code,
+            billing_street: order.billing_street,
+            billing_city: order.billing_city,
+            billing_postal_code: order.billing_postal_code,
+            billing_country_code: order.billing_country_code,
+            version: order.version,
+            deleted_at: order.deleted_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default, ToSchema)]
+pub struct BulkDeleteReport {
+    pub deleted: Vec<i32>,
+    /// Ids that did not exist
+    pub missing: Vec<i32>,
+    /// Ids kept because other records still reference them
+    pub blocked: Vec<BlockedId>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BlockedId {
+    pub id: i32,
+    /// Table holding the references, e.g. `orders`
+    pub referenced_by: String,
+    /// Ids of the live rows referencing it
+    pub dependents: Vec<i32>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct AuditEntryDto {
+    pub id: i64,
+    /// `X-Actor` of the request, `anonymous` without one, `system` for background jobs
+    pub actor: String,
+    /// `create`, `update`, `delete`, `restore` or `purge`
+    pub action: String,
+    /// Table of the record, e.g. `orders`
+    pub resource: String,
+    pub resource_id: i32,
+    /// The record before the write, shaped like its v1 response; absent for creates
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<Value>,
+    /// The record after the write; absent for purges
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<Value>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEntry> for AuditEntryDto {
+    fn from(entry: AuditEntry) -> Self {
+        AuditEntryDto {
+            id: entry.id,
+            actor: entry.actor,
+            action: entry.action,
+            resource: entry.resource,
+            resource_id: entry.resource_id,
+            before: entry.before_data.map(|data| data.0),
+            after: entry.after_data.map(|data| data.0),
+            request_id: entry.request_id,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+// One write to a record, reduced to the fields it changed
+#[derive(Serialize, Debug, ToSchema)]
+pub struct HistoryEntryDto {
+    /// Id of the audit log entry
+    pub id: i64,
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    /// `create`, `update`, `delete`, `restore` or `purge`
+    pub action: String,
+    pub request_id: Option<String>,
+    /// Fields whose value differs between the snapshots before and after the write
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    /// Value before the write, `null` for creates
+    #[schema(value_type = Object)]
+    pub from: Value,
+    /// Value after the write, `null` for purges
+    #[schema(value_type = Object)]
+    pub to: Value,
+}
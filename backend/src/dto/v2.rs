@@ -0,0 +1,72 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::models::order::Order;
+
+// Orders embed their address snapshots as objects instead of prefixed columns
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OrderV2Dto {
+    pub id: i32,
+    pub customer_id: i32,
+    pub product_id: i32,
+    pub quantity: i32,
+    pub order_date: NaiveDate,
+    pub shipping_address: Option<OrderAddressDto>,
+    pub billing_address: Option<OrderAddressDto>,
+    /// Row version, also sent as the `ETag`
+    pub version: i32,
+    /// When the record was moved to the trash, if it was
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Address copied onto the order when it was placed
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OrderAddressDto {
+    pub street: String,
+    pub city: String,
+    pub postal_code: String,
+    pub country_code: String,
+}
+
+impl OrderAddressDto {
+    // Snapshot columns are written together, so a partial snapshot is treated as absent
+    fn from_columns(
+        street: Option<String>,
+        city: Option<String>,
+        postal_code: Option<String>,
+        country_code: Option<String>,
+    ) -> Option<Self> {
+        Some(OrderAddressDto {
+            street: street?,
+            city: city?,
+            postal_code: postal_code?,
+            country_code: country_code?,
+        })
+    }
+}
+
+impl From<Order> for OrderV2Dto {
+    fn from(order: Order) -> Self {
+        OrderV2Dto {
+            id: order.id,
+            customer_id: order.customer_id,
+            product_id: order.product_id,
+            quantity: order.quantity,
+            order_date: order.order_date,
+            shipping_address: OrderAddressDto::from_columns(
+                order.shipping_street,
+                order.shipping_city,
+                order.shipping_postal_code,
+                order.shipping_country_code,
+            ),
+            billing_address: OrderAddressDto::from_columns(
+                order.billing_street,
+                order.billing_city,
+                order.billing_postal_code,
+                order.billing_country_code,
+            ),
+            version: order.version,
+            deleted_at: order.deleted_at,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use axum::{Json, extract::{State, Path, OriginalUri}, http::{HeaderMap, StatusCode}};
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool, Transaction};
+use crate::models::address::{CustomerAddress, CreateAddress};
+use crate::audit::{self, AuditAction};
+use crate::dto::v1::AddressDto;
+use crate::utils::{AppError, json_response, content_range_header, validate_customer_exists, map_constraint_violation, map_fetch_error, created_response, etag_header, if_match, lock_version, inserted_id};
+use crate::metrics::TimedQuery;
+use validator::Validate;
+use tracing::{info, error};
+
+/// List all addresses of a customer
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers/{id}/addresses",
+    tag = "customers",
+    params(("id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "All addresses of the customer", body = AddressListResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_addresses(Path(customer_id): Path<i32>, State(pool): State<MySqlPool>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    // Check if the customer exists
+    validate_customer_exists(&pool, customer_id).await?;
+
+    let addresses = sqlx::query_as::<_, CustomerAddress>("SELECT * FROM customer_addresses WHERE customer_id = ?")
+        .bind(customer_id)
+        .fetch_all(&pool)
+        .timed("list_addresses")
+        .await
+        .map_err(|e| {
+            error!(resource = "addresses", customer_id, error = ?e, "Failed to fetch addresses");
+            AppError::DatabaseError(e)
+        })?;
+
+    info!(resource = "addresses", customer_id, count = addresses.len(), "Successfully fetched addresses");
+    let headers = content_range_header("addresses", addresses.len());
+    Ok((headers, json_response(addresses.into_iter().map(AddressDto::from).collect::<Vec<_>>())))
+}
+
+/// Get a specific address of a customer
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers/{id}/addresses/{address_id}",
+    tag = "customers",
+    params(("id" = i32, Path, description = "Customer id"), ("address_id" = i32, Path, description = "Address id")),
+    responses(
+        (status = 200, description = "The address", body = AddressResponse,
+            headers(("ETag" = String, description = "Current version of the address"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_address(Path((customer_id, id)): Path<(i32, i32)>, State(pool): State<MySqlPool>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    // Check if the customer exists
+    validate_customer_exists(&pool, customer_id).await?;
+
+    let address = fetch_address(&pool, customer_id, id).await?;
+    Ok((etag_header(address.version), json_response(AddressDto::from(address))))
+}
+
+/// Create a new address for a customer
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/{id}/addresses",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    request_body = CreateAddress,
+    responses(
+        (status = 201, description = "Address created", body = AddressResponse,
+            headers(("Location" = String, description = "URL of the new address"), ("ETag" = String, description = "Version of the new address"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_address(Path(customer_id): Path<i32>, OriginalUri(uri): OriginalUri, State(pool): State<MySqlPool>, Json(address): Json<CreateAddress>) -> Result<(StatusCode, HeaderMap, Json<Value>), AppError> {
+    // Validate the input
+    address.validate().map_err(AppError::ValidationError)?;
+
+    // Check if the customer exists
+    validate_customer_exists(&pool, customer_id).await?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    if address.is_default {
+        clear_default_address(&mut tx, customer_id, &address.address_type).await?;
+    }
+
+    // Insert the new address into the database
+    let result = sqlx::query("INSERT INTO customer_addresses (customer_id, address_type, street, city, postal_code, country_code, is_default) VALUES (?, ?, ?, ?, ?, ?, ?)")
+        .bind(customer_id)
+        .bind(&address.address_type)
+        .bind(&address.street)
+        .bind(&address.city)
+        .bind(&address.postal_code)
+        .bind(&address.country_code)
+        .bind(address.is_default)
+        .execute(&mut *tx)
+        .timed("create_address")
+        .await
+        .map_err(|e| map_constraint_violation(e, "address"))?;
+
+    let id = inserted_id(&result);
+    audit::record(&mut tx, AuditAction::Create, "customer_addresses", id, None).await?;
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    let address = fetch_address(&pool, customer_id, id).await?;
+    Ok(created_response(&uri, address.id, address.version, AddressDto::from(address)))
+}
+
+/// Update an existing address of a customer
+#[utoipa::path(
+    put,
+    path = "/api/v1/customers/{id}/addresses/{address_id}",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("address_id" = i32, Path, description = "Address id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body = CreateAddress,
+    responses(
+        (status = 200, description = "Address updated", body = AddressResponse,
+            headers(("ETag" = String, description = "New version of the address"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_address(Path((customer_id, id)): Path<(i32, i32)>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(address): Json<CreateAddress>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    // Validate the input
+    address.validate().map_err(AppError::ValidationError)?;
+
+    // Check if the customer exists
+    validate_customer_exists(&pool, customer_id).await?;
+
+    // The address must belong to this customer before its version is compared
+    fetch_address(&pool, customer_id, id).await?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    // Check the address still has the version the client edited
+    lock_version(&mut tx, "customer_addresses", "address", id, if_match(&headers).as_deref()).await?;
+
+    if address.is_default {
+        clear_default_address(&mut tx, customer_id, &address.address_type).await?;
+    }
+
+    // Update the address in the database
+    let before = audit::snapshot(&mut tx, "customer_addresses", id).await?;
+    let result = sqlx::query("UPDATE customer_addresses SET address_type = ?, street = ?, city = ?, postal_code = ?, country_code = ?, is_default = ?, version = version + 1 WHERE id = ? AND customer_id = ?")
+        .bind(&address.address_type)
+        .bind(&address.street)
+        .bind(&address.city)
+        .bind(&address.postal_code)
+        .bind(&address.country_code)
+        .bind(address.is_default)
+        .bind(id)
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .timed("update_address")
+        .await
+        .map_err(|e| map_constraint_violation(e, "address"))?;
+
+    // Check if the address was actually found
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("address", id));
+    }
+    audit::record(&mut tx, AuditAction::Update, "customer_addresses", id, before).await?;
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    let address = fetch_address(&pool, customer_id, id).await?;
+    Ok((etag_header(address.version), json_response(AddressDto::from(address))))
+}
+
+/// Delete an address of a customer
+#[utoipa::path(
+    delete,
+    path = "/api/v1/customers/{id}/addresses/{address_id}",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("address_id" = i32, Path, description = "Address id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the deletion is based on"),
+    ),
+    responses(
+        (status = 200, description = "Address deleted", body = MessageResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_address(Path((customer_id, id)): Path<(i32, i32)>, State(pool): State<MySqlPool>, headers: HeaderMap) -> Result<Json<Value>, AppError> {
+    // Check if the customer exists
+    validate_customer_exists(&pool, customer_id).await?;
+
+    // The address must belong to this customer before its version is compared
+    fetch_address(&pool, customer_id, id).await?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    // Check the address still has the version the client saw
+    lock_version(&mut tx, "customer_addresses", "address", id, if_match(&headers).as_deref()).await?;
+
+    let before = audit::snapshot(&mut tx, "customer_addresses", id).await?;
+    let result = sqlx::query("DELETE FROM customer_addresses WHERE id = ? AND customer_id = ?")
+        .bind(id)
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .timed("delete_address")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    // Check if the address was actually deleted
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("address", id));
+    }
+    audit::record(&mut tx, AuditAction::Delete, "customer_addresses", id, before).await?;
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    Ok(json_response("Address deleted successfully"))
+}
+
+// Only one default address per customer and address type
+async fn clear_default_address(tx: &mut Transaction<'_, MySql>, customer_id: i32, address_type: &str) -> Result<(), AppError> {
+    let ids: Vec<i32> = sqlx::query_scalar("SELECT id FROM customer_addresses WHERE customer_id = ? AND address_type = ? AND is_default = TRUE")
+        .bind(customer_id)
+        .bind(address_type)
+        .fetch_all(&mut **tx)
+        .timed("clear_default_address")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    let before = audit::snapshots(tx, "customer_addresses", &ids).await?;
+
+    let _ = sqlx::query("UPDATE customer_addresses SET is_default = FALSE, version = version + 1 WHERE customer_id = ? AND address_type = ? AND is_default = TRUE")
+        .bind(customer_id)
+        .bind(address_type)
+        .execute(&mut **tx)
+        .timed("clear_default_address")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    audit::record_all(tx, AuditAction::Update, "customer_addresses", before).await
+}
+
+// Load an address for the response body, answering 404 when the customer has no such address
+async fn fetch_address(pool: &MySqlPool, customer_id: i32, id: i32) -> Result<CustomerAddress, AppError> {
+    sqlx::query_as::<_, CustomerAddress>("SELECT * FROM customer_addresses WHERE id = ? AND customer_id = ?")
+        .bind(id)
+        .bind(customer_id)
+        .fetch_one(pool)
+        .timed("get_address")
+        .await
+        .map_err(|e| map_fetch_error(e, "address", id))
+}
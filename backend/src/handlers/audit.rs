@@ -0,0 +1,165 @@
+use axum::{Json, extract::{State, Path, Query}, http::HeaderMap};
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use crate::audit;
+use crate::models::audit::{AuditEntry, AuditLogParams};
+use crate::dto::v1::AuditEntryDto;
+use crate::utils::{AppError, json_response, content_range_page_header, map_fetch_error};
+use crate::metrics::TimedQuery;
+use tracing::{info, error};
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// List audit log entries, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit-log",
+    tag = "audit",
+    params(AuditLogParams),
+    responses(
+        (status = 200, description = "One page of matching entries", body = AuditLogResponse,
+            headers(("Content-Range" = String, description = "Position of the page among all matching entries"))),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_audit_log(State(pool): State<MySqlPool>, Query(params): Query<AuditLogParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+
+    let mut count = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM audit_log");
+    push_filters(&mut count, &params);
+    let total: i64 = count
+        .build_query_scalar()
+        .fetch_one(&pool)
+        .timed("list_audit_log")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    let mut query = QueryBuilder::<MySql>::new("SELECT * FROM audit_log");
+    push_filters(&mut query, &params);
+    query.push(" ORDER BY id DESC LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+    let entries = query
+        .build_query_as::<AuditEntry>()
+        .fetch_all(&pool)
+        .timed("list_audit_log")
+        .await
+        .map_err(|e| {
+            error!(resource = "audit_log", error = ?e, "Failed to fetch audit log");
+            AppError::DatabaseError(e)
+        })?;
+
+    info!(resource = "audit_log", count = entries.len(), total, "Successfully fetched audit log");
+    let headers = content_range_page_header("audit-log", offset.into(), entries.len(), total as u64);
+    Ok((headers, json_response(entries.into_iter().map(AuditEntryDto::from).collect::<Vec<_>>())))
+}
+
+/// Get a single audit log entry
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit-log/{id}",
+    tag = "audit",
+    params(("id" = i64, Path, description = "Audit log entry id")),
+    responses(
+        (status = 200, description = "The entry", body = AuditEntryResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_audit_entry(Path(id): Path<i64>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
+    let entry = sqlx::query_as::<_, AuditEntry>("SELECT * FROM audit_log WHERE id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .timed("get_audit_entry")
+        .await
+        .map_err(|e| map_fetch_error(e, "audit log entry", id))?;
+
+    Ok(json_response(AuditEntryDto::from(entry)))
+}
+
+/// Field-by-field change history of a product, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/products/{id}/history",
+    tag = "products",
+    params(("id" = i32, Path, description = "Product id")),
+    responses(
+        (status = 200, description = "Every recorded write to the product with the fields it changed", body = HistoryResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn product_history(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
+    record_history(&pool, "products", "product", id).await
+}
+
+/// Field-by-field change history of a customer, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers/{id}/history",
+    tag = "customers",
+    params(("id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Every recorded write to the customer with the fields it changed", body = HistoryResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn customer_history(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
+    record_history(&pool, "customers", "customer", id).await
+}
+
+/// Field-by-field change history of an order, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/orders/{id}/history",
+    tag = "orders",
+    params(("id" = i32, Path, description = "Order id")),
+    responses(
+        (status = 200, description = "Every recorded write to the order with the fields it changed", body = HistoryResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn order_history(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
+    record_history(&pool, "orders", "order", id).await
+}
+
+// History of a record, trashed or not; one that was purged still has its entries, so only a
+// record that never existed answers 404
+async fn record_history(pool: &MySqlPool, table: &'static str, resource: &'static str, id: i32) -> Result<Json<Value>, AppError> {
+    let history = audit::history(pool, table, id).await?;
+    if history.is_empty() {
+        let exists: bool = sqlx::query_scalar(&format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?)", table))
+            .bind(id)
+            .fetch_one(pool)
+            .timed("record_history")
+            .await
+            .map_err(AppError::DatabaseError)?;
+        if !exists {
+            return Err(AppError::not_found(resource, id));
+        }
+    }
+
+    Ok(json_response(history))
+}
+
+// WHERE clause shared by the count and the page query
+fn push_filters(query: &mut QueryBuilder<'_, MySql>, params: &AuditLogParams) {
+    query.push(" WHERE TRUE");
+    if let Some(resource) = &params.resource {
+        query.push(" AND resource = ").push_bind(resource.clone());
+    }
+    if let Some(resource_id) = params.resource_id {
+        query.push(" AND resource_id = ").push_bind(resource_id);
+    }
+    if let Some(actor) = &params.actor {
+        query.push(" AND actor = ").push_bind(actor.clone());
+    }
+    if let Some(from) = params.from {
+        query.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = params.to {
+        query.push(" AND created_at < ").push_bind(to);
+    }
+}
@@ -1,122 +1,482 @@
-use axum::{Json, extract::{State, Path}, http::HeaderMap};
+use axum::{Json, extract::{State, Path, OriginalUri, Query}, http::{HeaderMap, StatusCode}};
 use serde_json::Value;
-use sqlx::MySqlPool;
-use crate::models::customer::{Customer, CreateCustomer};
-use crate::utils::{AppError, json_response, content_range_header, validate_customer_exists};
+use std::borrow::Cow;
+use sqlx::{MySql, MySqlConnection, MySqlPool, QueryBuilder};
+use crate::models::{BulkDeleteParams, TrashParams};
+use crate::models::customer::{Customer, CreateCustomer, BulkUpdateCustomer, MergeCustomer, PatchCustomer};
+use crate::audit::{self, AuditAction};
+use crate::dto::v1::CustomerDto;
+use crate::config::{DeletionConfig, DeletionPolicy};
+use crate::utils::{AppError, json_response, content_range_header, map_constraint_violation, map_fetch_error, validate_patch, push_patch_field, created_response, etag, etag_header, if_match, lock_version, owned_ids, soft_delete, restore, delete_by_ids, validate_items, validate_unique_ids, bulk_item_error, fetch_by_ids, inserted_id};
+use crate::metrics::TimedQuery;
 use validator::Validate;
 use tracing::{info, error};
 use validator::{ValidationErrors, ValidationError};
 
 //noinspection ALL
 /// List all customers
-pub async fn list_customers(State(pool): State<MySqlPool>) -> Result<(HeaderMap, Json<Value>), AppError> {
-    let customers = sqlx::query_as::<_, Customer>("SELECT * FROM customers")
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers",
+    tag = "customers",
+    params(TrashParams),
+    responses(
+        (status = 200, description = "All customers", body = CustomerListResponse),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_customers(State(pool): State<MySqlPool>, Query(trash): Query<TrashParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let customers = sqlx::query_as::<_, Customer>(&format!("SELECT * FROM customers WHERE {}", trash.condition()))
         .fetch_all(&pool)
+        .timed("list_customers")
         .await
          .map_err(|e| {
-	    error!("Failed to fetch customers: {:?}", e);
+	    error!(resource = "customers", error = ?e, "Failed to fetch customers");
 	    AppError::DatabaseError(e)
 	})?;
 
-    info!("Successfully fetched {} customers", customers.len());
+    info!(resource = "customers", count = customers.len(), "Successfully fetched customers");
     let headers = content_range_header("customers", customers.len());
-    Ok((headers, json_response(customers)))
+    Ok((headers, json_response(customers.into_iter().map(CustomerDto::from).collect::<Vec<_>>())))
 }
 
 //noinspection ALL
 /// Get a specific customer by ID
-pub async fn get_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
-    let customer = sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE id = ?")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| AppError::NotFound)?;
-
-    Ok(json_response(customer))
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers/{id}",
+    tag = "customers",
+    params(("id" = i32, Path, description = "Customer id"), TrashParams),
+    responses(
+        (status = 200, description = "The customer", body = CustomerResponse,
+            headers(("ETag" = String, description = "Current version of the customer"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>, Query(trash): Query<TrashParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let customer = fetch_customer(&pool, id).await?;
+    if !trash.shows(customer.deleted_at) {
+        return Err(AppError::not_found("customer", id));
+    }
+    Ok((etag_header(customer.version), json_response(CustomerDto::from(customer))))
 }
 
 /// Create a new customer
-pub async fn create_customer(State(pool): State<MySqlPool>, Json(customer): Json<CreateCustomer>) -> Result<Json<Value>, AppError> {
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers",
+    tag = "customers",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = CreateCustomer,
+    responses(
+        (status = 201, description = "Customer created", body = CustomerResponse,
+            headers(("Location" = String, description = "URL of the new customer"), ("ETag" = String, description = "Version of the new customer"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Conflict with existing data", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_customer(OriginalUri(uri): OriginalUri, State(pool): State<MySqlPool>, Json(customer): Json<CreateCustomer>) -> Result<(StatusCode, HeaderMap, Json<Value>), AppError> {
     // Validate the input
     customer.validate().map_err(AppError::ValidationError)?;
 
     // Insert the new customer into the database
-    let _ = sqlx::query("INSERT INTO customers (name, email, address) VALUES (?, ?, ?)")
-        .bind(&customer.name)
-        .bind(&customer.email)
-        .bind(&customer.address)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    let id = insert_customer(&mut tx, &customer).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
 
-    Ok(json_response("Customer created successfully"))
+    let customer = fetch_customer(&pool, id).await?;
+    Ok(created_response(&uri, customer.id, customer.version, CustomerDto::from(customer)))
 }
 
 /// Update an existing customer
-pub async fn update_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>, Json(customer): Json<CreateCustomer>) -> Result<Json<Value>, AppError> {
+#[utoipa::path(
+    put,
+    path = "/api/v1/customers/{id}",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body = CreateCustomer,
+    responses(
+        (status = 200, description = "Customer updated", body = CustomerResponse,
+            headers(("ETag" = String, description = "New version of the customer"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Conflict with existing data", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(customer): Json<CreateCustomer>) -> Result<(HeaderMap, Json<Value>), AppError> {
     // Validate the input
     customer.validate().map_err(AppError::ValidationError)?;
 
-    // Check if the customer exists
-    validate_customer_exists(&pool, id).await?;
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    replace_customer(&mut tx, id, &customer, if_match(&headers).as_deref()).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
 
-    // Update the customer in the database
-    let _ = sqlx::query("UPDATE customers SET name = ?, email = ?, address = ? WHERE id = ?")
-        .bind(&customer.name)
-        .bind(&customer.email)
-        .bind(&customer.address)
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+    let customer = fetch_customer(&pool, id).await?;
+    Ok((etag_header(customer.version), json_response(CustomerDto::from(customer))))
+}
+
+/// Partially update a customer (JSON Merge Patch)
+#[utoipa::path(
+    patch,
+    path = "/api/v1/customers/{id}",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body(content = PatchCustomer, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Customer updated", body = CustomerResponse,
+            headers(("ETag" = String, description = "New version of the customer"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Conflict with existing data", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(patch): Json<PatchCustomer>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    // Validate only the supplied fields
+    validate_patch(&patch, &patch.cleared_required())?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    // Check the customer exists and still has the version the client edited
+    lock_version(&mut tx, "customers", "customer", id, if_match(&headers).as_deref()).await?;
+
+    // Update only the supplied columns
+    let mut query = QueryBuilder::<MySql>::new("UPDATE customers SET ");
+    let mut set = query.separated(", ");
+    let changed = [
+        push_patch_field(&mut set, "name", patch.name),
+        push_patch_field(&mut set, "email", patch.email),
+        push_patch_field(&mut set, "address", patch.address),
+    ]
+    .contains(&true);
+
+    if changed {
+        let before = audit::snapshot(&mut tx, "customers", id).await?;
+        set.push("version = version + 1");
+        query.push(" WHERE id = ").push_bind(id);
+        query
+            .build()
+            .execute(&mut *tx)
+            .timed("patch_customer")
+            .await
+            .map_err(|e| map_constraint_violation(e, "customer"))?;
+        audit::record(&mut tx, AuditAction::Update, "customers", id, before).await?;
+    }
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
 
-    Ok(json_response("Customer updated successfully"))
+    let customer = fetch_customer(&pool, id).await?;
+    Ok((etag_header(customer.version), json_response(CustomerDto::from(customer))))
 }
 
-/// Delete a customer by ID
-pub async fn delete_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
-    // Check if the customer exists
-    validate_customer_exists(&pool, id).await?;
+/// Create many customers in one transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/bulk-create",
+    tag = "customers",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<CreateCustomer>,
+    responses(
+        (status = 201, description = "The created customers, in request order", body = CustomerListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Conflict with existing data, or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_create_customers(State(pool): State<MySqlPool>, Json(items): Json<Vec<CreateCustomer>>) -> Result<(StatusCode, Json<Value>), AppError> {
+    // Validate every item before writing any of them
+    validate_items(items.iter())?;
 
-    // Delete the customer from the database
-    let _ = sqlx::query("DELETE FROM customers WHERE id = ?")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    let mut ids = Vec::with_capacity(items.len());
+    for (index, customer) in items.iter().enumerate() {
+        ids.push(insert_customer(&mut tx, customer).await.map_err(|e| bulk_item_error(index, e))?);
+    }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    info!(resource = "customers", created = ids.len(), "Bulk created customers");
+
+    let customers = fetch_by_ids::<Customer>(&pool, "customers", &ids).await?;
+    Ok((StatusCode::CREATED, json_response(customers.into_iter().map(CustomerDto::from).collect::<Vec<_>>())))
+}
+
+/// Overwrite many customers in one transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/bulk-update",
+    tag = "customers",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<BulkUpdateCustomer>,
+    responses(
+        (status = 200, description = "The updated customers, in request order", body = CustomerListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Conflict with existing data, or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "A customer changed since the given version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_update_customers(State(pool): State<MySqlPool>, Json(items): Json<Vec<BulkUpdateCustomer>>) -> Result<Json<Value>, AppError> {
+    // Validate every item before writing any of them
+    validate_items(items.iter())?;
+    validate_unique_ids(items.iter().map(|item| item.id))?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    for (index, item) in items.iter().enumerate() {
+        let if_match = item.version.map(|version| vec![etag(version)]);
+        replace_customer(&mut tx, item.id, &item.customer, if_match.as_deref())
+            .await
+            .map_err(|e| bulk_item_error(index, e))?;
+    }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    info!(resource = "customers", updated = items.len(), "Bulk updated customers");
+
+    let ids: Vec<i32> = items.iter().map(|item| item.id).collect();
+    let customers = fetch_by_ids::<Customer>(&pool, "customers", &ids).await?;
+    Ok(json_response(customers.into_iter().map(CustomerDto::from).collect::<Vec<_>>()))
+}
+
+/// Move a customer to the trash
+#[utoipa::path(
+    delete,
+    path = "/api/v1/customers/{id}",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the deletion is based on"),
+    ),
+    responses(
+        (status = 200, description = "Customer deleted", body = MessageResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Still referenced by live orders, which are listed (under the `block` policy)", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>, State(deletion): State<DeletionConfig>, headers: HeaderMap) -> Result<Json<Value>, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    soft_delete(&mut tx, "customers", "customer", id, if_match(&headers).as_deref(), deletion.customer_orders).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
 
     Ok(json_response("Customer deleted successfully"))
 }
 
-/// Delete multiple customers by IDs
-pub async fn delete_customers(State(pool): State<MySqlPool>, Json(ids): Json<Vec<i32>>) -> Result<Json<Value>, AppError> {
-    if ids.is_empty() {
+/// Restore a customer from the trash
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/{id}/restore",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Customer id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the restore is based on"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    responses(
+        (status = 200, description = "Customer restored", body = CustomerResponse,
+            headers(("ETag" = String, description = "New version of the customer"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The customer is not deleted, or its email is now used by another customer; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn restore_customer(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    restore(&mut tx, "customers", "customer", id, if_match(&headers).as_deref()).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    info!(resource = "customers", id, "Restored customer");
+
+    let customer = fetch_customer(&pool, id).await?;
+    Ok((etag_header(customer.version), json_response(CustomerDto::from(customer))))
+}
+
+/// Merge a duplicate customer into this one, moving its orders and trashing the duplicate
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/{id}/merge",
+    tag = "customers",
+    params(
+        ("id" = i32, Path, description = "Id of the customer to keep"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    request_body = MergeCustomer,
+    responses(
+        (status = 200, description = "The surviving customer", body = CustomerResponse),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn merge_customers(Path(id): Path<i32>, State(pool): State<MySqlPool>, Json(merge): Json<MergeCustomer>) -> Result<Json<Value>, AppError> {
+    // Validate the input
+    merge.validate().map_err(AppError::ValidationError)?;
+
+    if merge.duplicate_id == id {
         let mut errors = ValidationErrors::new();
-        errors.add("ids", ValidationError::new("No IDs provided"));
+        errors.add("duplicate_id", ValidationError::new("self_merge").with_message(Cow::from("Cannot merge a customer into itself")));
         return Err(AppError::ValidationError(errors));
     }
 
-    // Construct the query with placeholders for each ID
-    let query = format!(
-        "DELETE FROM customers WHERE id IN ({})",
-        ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
-    );
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    // Lock both customers so concurrent merges or edits cannot interleave
+    let locked = sqlx::query_scalar::<_, i32>("SELECT id FROM customers WHERE id IN (?, ?) AND deleted_at IS NULL FOR UPDATE")
+        .bind(id)
+        .bind(merge.duplicate_id)
+        .fetch_all(&mut *tx)
+        .timed("merge_customers")
+        .await
+        .map_err(AppError::DatabaseError)?;
 
-    // Execute the query
-    let mut query = sqlx::query(&query);
-    for id in ids.iter() {
-        query = query.bind(id);
+    for customer_id in [id, merge.duplicate_id] {
+        if !locked.contains(&customer_id) {
+            return Err(AppError::not_found("customer", customer_id));
+        }
     }
 
-    let result = query
-        .execute(&pool)
+    // Move the duplicate's live orders onto the surviving customer; trashed ones stay with the
+    // duplicate and are purged along with it
+    let order_ids = sqlx::query_scalar::<_, i32>("SELECT id FROM orders WHERE customer_id = ? AND deleted_at IS NULL ORDER BY id FOR UPDATE")
+        .bind(merge.duplicate_id)
+        .fetch_all(&mut *tx)
+        .timed("merge_customers")
         .await
         .map_err(AppError::DatabaseError)?;
+    let orders = audit::snapshots(&mut tx, "orders", &order_ids).await?;
+    let moved = sqlx::query("UPDATE orders SET customer_id = ?, version = version + 1 WHERE customer_id = ? AND deleted_at IS NULL")
+        .bind(id)
+        .bind(merge.duplicate_id)
+        .execute(&mut *tx)
+        .timed("merge_customers")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    audit::record_all(&mut tx, AuditAction::Update, "orders", orders).await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
+    // Keep the duplicate's addresses, without overriding the surviving defaults
+    let address_ids = owned_ids(&mut tx, "customer_addresses", merge.duplicate_id).await?;
+    let addresses = audit::snapshots(&mut tx, "customer_addresses", &address_ids).await?;
+    let _ = sqlx::query("UPDATE customer_addresses SET customer_id = ?, is_default = FALSE, version = version + 1 WHERE customer_id = ?")
+        .bind(id)
+        .bind(merge.duplicate_id)
+        .execute(&mut *tx)
+        .timed("merge_customers")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    audit::record_all(&mut tx, AuditAction::Update, "customer_addresses", addresses).await?;
+
+    // Move the duplicate customer to the trash, now that nothing references it
+    soft_delete(&mut tx, "customers", "customer", merge.duplicate_id, None, DeletionPolicy::Block).await?;
+
+    let customer = sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .timed("merge_customers")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    tx.commit().await.map_err(|e| {
+        error!(resource = "customers", id, duplicate_id = merge.duplicate_id, error = ?e, "Failed to commit customer merge");
+        AppError::DatabaseError(e)
+    })?;
+
+    info!(resource = "customers", id, duplicate_id = merge.duplicate_id, orders_moved = moved.rows_affected(), "Merged duplicate customer");
+    Ok(json_response(CustomerDto::from(customer)))
+}
+
+/// Move multiple customers to the trash by IDs
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/bulk-delete",
+    tag = "customers",
+    params(
+        BulkDeleteParams,
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    request_body = Vec<i32>,
+    responses(
+        (status = 200, description = "Which ids were deleted, missing or still referenced", body = BulkDeleteResponse),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "With `atomic`: some ids do not exist, nothing was deleted", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "With `atomic`: some ids are still referenced, nothing was deleted; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_customers(State(pool): State<MySqlPool>, State(deletion): State<DeletionConfig>, Query(params): Query<BulkDeleteParams>, Json(ids): Json<Vec<i32>>) -> Result<Json<Value>, AppError> {
+    let report = delete_by_ids(&pool, "customers", "customer", &ids, params.atomic, deletion.customer_orders).await?;
 
-    // Return the deleted IDs in the `data` field
-    Ok(json_response(ids))
+    info!(
+        resource = "customers",
+        requested = ids.len(),
+        deleted = report.deleted.len(),
+        missing = report.missing.len(),
+        blocked = report.blocked.len(),
+        "Bulk deleted customers"
+    );
+
+    Ok(json_response(report))
+}
+
+// Load a customer for the response body, answering 404 when it does not exist
+async fn fetch_customer(pool: &MySqlPool, id: i32) -> Result<Customer, AppError> {
+    sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .timed("get_customer")
+        .await
+        .map_err(|e| map_fetch_error(e, "customer", id))
+}
+
+// Store a new customer inside the caller's transaction, returning its id
+async fn insert_customer(conn: &mut MySqlConnection, customer: &CreateCustomer) -> Result<i32, AppError> {
+    let result = sqlx::query("INSERT INTO customers (name, email, address) VALUES (?, ?, ?)")
+        .bind(&customer.name)
+        .bind(&customer.email)
+        .bind(&customer.address)
+        .execute(&mut *conn)
+        .timed("create_customer")
+        .await
+        .map_err(|e| map_constraint_violation(e, "customer"))?;
+
+    let id = inserted_id(&result);
+    audit::record(conn, AuditAction::Create, "customers", id, None).await?;
+    Ok(id)
 }
+
+// Overwrite a customer inside the caller's transaction
+async fn replace_customer(conn: &mut MySqlConnection, id: i32, customer: &CreateCustomer, if_match: Option<&[String]>) -> Result<(), AppError> {
+    // Check the customer exists and still has the version the client edited
+    lock_version(&mut *conn, "customers", "customer", id, if_match).await?;
+
+    let before = audit::snapshot(&mut *conn, "customers", id).await?;
+    sqlx::query("UPDATE customers SET name = ?, email = ?, address = ?, version = version + 1 WHERE id = ?")
+        .bind(&customer.name)
+        .bind(&customer.email)
+        .bind(&customer.address)
+        .bind(id)
+        .execute(&mut *conn)
+        .timed("update_customer")
+        .await
+        .map_err(|e| map_constraint_violation(e, "customer"))?;
+    audit::record(conn, AuditAction::Update, "customers", id, before).await
+}
+
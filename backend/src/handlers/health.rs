@@ -0,0 +1,75 @@
+use axum::{Json, extract::State, http::StatusCode};
+use serde_json::{json, Value};
+use sqlx::MySqlPool;
+use std::time::Instant;
+use crate::db::migrations_applied;
+use tracing::warn;
+
+// State shared by the health endpoints
+#[derive(Clone)]
+pub struct HealthState {
+    pub pool: MySqlPool,
+    pub started_at: Instant,
+}
+
+impl HealthState {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool, started_at: Instant::now() }
+    }
+}
+
+/// Liveness: the process is up and serving requests
+pub async fn live() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: the database answers through the pool and all migrations are applied
+pub async fn ready(State(state): State<HealthState>) -> (StatusCode, Json<Value>) {
+    let (ready, checks) = readiness_checks(&state.pool).await;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(json!({
+        "status": if ready { "ready" } else { "unavailable" },
+        "checks": checks,
+    })))
+}
+
+/// Summary with version, uptime, pool statistics and readiness checks
+pub async fn health(State(state): State<HealthState>) -> (StatusCode, Json<Value>) {
+    let (ready, checks) = readiness_checks(&state.pool).await;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(json!({
+        "status": if ready { "ok" } else { "degraded" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_seconds": state.started_at.elapsed().as_secs(),
+        "pool": {
+            "size": state.pool.size(),
+            "idle": state.pool.num_idle(),
+            "max_connections": state.pool.options().get_max_connections(),
+        },
+        "checks": checks,
+    })))
+}
+
+async fn readiness_checks(pool: &MySqlPool) -> (bool, Value) {
+    let database = match sqlx::query("SELECT 1").execute(pool).await {
+        Ok(_) => "ok",
+        Err(e) => {
+            warn!(check = "database", error = ?e, "Readiness check failed, database unreachable");
+            "unreachable"
+        }
+    };
+
+    let migrations = match migrations_applied(pool).await {
+        Ok(true) => "ok",
+        Ok(false) => "pending",
+        Err(e) => {
+            warn!(check = "migrations", error = ?e, "Readiness check failed, cannot read migrations");
+            "unknown"
+        }
+    };
+
+    let ready = database == "ok" && migrations == "ok";
+    (ready, json!({ "database": database, "migrations": migrations }))
+}
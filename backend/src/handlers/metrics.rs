@@ -0,0 +1,34 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use metrics::gauge;
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::MySqlPool;
+use tracing::warn;
+
+// State of the `/metrics` endpoint
+#[derive(Clone)]
+pub struct MetricsState {
+    pub pool: MySqlPool,
+    pub handle: PrometheusHandle,
+}
+
+/// Render all metrics in the Prometheus text format
+pub async fn metrics(State(state): State<MetricsState>) -> impl IntoResponse {
+    // Pool utilisation is sampled at scrape time
+    let pool = &state.pool;
+    gauge!("db_pool_connections").set(pool.size() as f64);
+    gauge!("db_pool_idle_connections").set(pool.num_idle() as f64);
+    gauge!("db_pool_max_connections").set(pool.options().get_max_connections() as f64);
+
+    match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM products WHERE in_stock = FALSE AND deleted_at IS NULL")
+        .fetch_one(pool)
+        .await
+    {
+        Ok(count) => gauge!("products_out_of_stock").set(count as f64),
+        Err(e) => warn!(resource = "products", error = ?e, "Failed to count out-of-stock products"),
+    }
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.handle.render(),
+    )
+}
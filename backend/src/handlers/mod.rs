@@ -1,3 +1,41 @@
+pub mod addresses;
+pub mod audit;
 pub mod customers;
+pub mod health;
+pub mod metrics;
 pub mod orders;
 pub mod products;
+
+use axum::extract::FromRef;
+use sqlx::MySqlPool;
+use crate::cache::ProductCache;
+use crate::config::DeletionConfig;
+
+// State shared by the `/api` handlers; each extracts the part it needs
+#[derive(Clone)]
+pub struct ApiState {
+    pub pool: MySqlPool,
+    pub product_cache: ProductCache,
+    pub deletion: DeletionConfig,
+}
+
+impl FromRef<ApiState> for MySqlPool {
+    fn from_ref(state: &ApiState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<ApiState> for ProductCache {
+    fn from_ref(state: &ApiState) -> Self {
+        state.product_cache.clone()
+    }
+}
+
+impl FromRef<ApiState> for DeletionConfig {
+    fn from_ref(state: &ApiState) -> Self {
+        state.deletion
+    }
+}
+
+#[cfg(test)]
+mod tests;
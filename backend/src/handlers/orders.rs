@@ -1,38 +1,537 @@
-use axum::{Json, extract::{State, Path}, http::HeaderMap};
+use axum::{Json, extract::{State, Path, OriginalUri, Query}, http::{HeaderMap, StatusCode}};
 use serde_json::Value;
-use sqlx::MySqlPool;
-use crate::models::order::{Order, CreateOrder};
-use validator::{Validate, ValidationError, ValidationErrors};
-use crate::utils::{AppError, json_response, content_range_header, validate_customer_exists, validate_product_exists};
+use sqlx::{Executor, MySql, MySqlConnection, MySqlPool, QueryBuilder, query_builder::Separated};
+use crate::models::{BulkDeleteParams, TrashParams};
+use crate::models::order::{Order, CreateOrder, BulkUpdateOrder, PatchOrder};
+use crate::models::address::CustomerAddress;
+use crate::dto::{v1::OrderDto, v2::OrderV2Dto};
+use crate::audit::{self, AuditAction};
+use crate::config::DeletionPolicy;
+use crate::metrics::TimedQuery;
+use validator::{Validate, ValidationErrors};
+use crate::utils::{AppError, json_response, content_range_header, validate_customer_exists, validate_product_exists, map_constraint_violation, map_fetch_error, validate_patch, push_patch_field, created_response, etag, etag_header, if_match, lock_version, soft_delete, restore, delete_by_ids, validate_items, validate_unique_ids, bulk_item_error, fetch_by_ids, inserted_id};
 use tracing::{info, error};
+use metrics::counter;
 
 /// List all orders
-pub async fn list_orders(State(pool): State<MySqlPool>) -> Result<(HeaderMap, Json<Value>), AppError> {
-    let orders = sqlx::query_as::<_, Order>("SELECT * FROM orders")
-        .fetch_all(&pool)
+#[utoipa::path(
+    get,
+    path = "/api/v1/orders",
+    tag = "orders",
+    params(TrashParams),
+    responses(
+        (status = 200, description = "All orders", body = OrderListResponse),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_orders(State(pool): State<MySqlPool>, Query(trash): Query<TrashParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let orders = fetch_orders(&pool, &trash).await?;
+    let headers = content_range_header("orders", orders.len());
+    Ok((headers, json_response(orders.into_iter().map(OrderDto::from).collect::<Vec<_>>())))
+}
+
+/// List all orders with nested address snapshots
+#[utoipa::path(
+    get,
+    path = "/api/v2/orders",
+    tag = "orders",
+    params(TrashParams),
+    responses(
+        (status = 200, description = "All orders", body = OrderV2ListResponse),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_orders_v2(State(pool): State<MySqlPool>, Query(trash): Query<TrashParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let orders = fetch_orders(&pool, &trash).await?;
+    let headers = content_range_header("orders", orders.len());
+    Ok((headers, json_response(orders.into_iter().map(OrderV2Dto::from).collect::<Vec<_>>())))
+}
+
+/// Get a specific order by ID
+#[utoipa::path(
+    get,
+    path = "/api/v1/orders/{id}",
+    tag = "orders",
+    params(("id" = i32, Path, description = "Order id"), TrashParams),
+    responses(
+        (status = 200, description = "The order", body = OrderResponse,
+            headers(("ETag" = String, description = "Current version of the order"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, Query(trash): Query<TrashParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = fetch_order(&pool, id).await?;
+    if !trash.shows(order.deleted_at) {
+        return Err(AppError::not_found("order", id));
+    }
+    Ok((etag_header(order.version), json_response(OrderDto::from(order))))
+}
+
+/// Get a specific order by ID with nested address snapshots
+#[utoipa::path(
+    get,
+    path = "/api/v2/orders/{id}",
+    tag = "orders",
+    params(("id" = i32, Path, description = "Order id"), TrashParams),
+    responses(
+        (status = 200, description = "The order", body = OrderV2Response,
+            headers(("ETag" = String, description = "Current version of the order"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_order_v2(Path(id): Path<i32>, State(pool): State<MySqlPool>, Query(trash): Query<TrashParams>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = fetch_order(&pool, id).await?;
+    if !trash.shows(order.deleted_at) {
+        return Err(AppError::not_found("order", id));
+    }
+    Ok((etag_header(order.version), json_response(OrderV2Dto::from(order))))
+}
+
+/// Create a new order
+#[utoipa::path(
+    post,
+    path = "/api/v1/orders",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = CreateOrder,
+    responses(
+        (status = 201, description = "Order created", body = OrderResponse,
+            headers(("Location" = String, description = "URL of the new order"), ("ETag" = String, description = "Version of the new order"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_order(OriginalUri(uri): OriginalUri, State(pool): State<MySqlPool>, Json(order): Json<CreateOrder>) -> Result<(StatusCode, HeaderMap, Json<Value>), AppError> {
+    let order = insert_order(&pool, &order).await?;
+    Ok(created_response(&uri, order.id, order.version, OrderDto::from(order)))
+}
+
+/// Create a new order, responding with nested address snapshots
+#[utoipa::path(
+    post,
+    path = "/api/v2/orders",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = CreateOrder,
+    responses(
+        (status = 201, description = "Order created", body = OrderV2Response,
+            headers(("Location" = String, description = "URL of the new order"), ("ETag" = String, description = "Version of the new order"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_order_v2(OriginalUri(uri): OriginalUri, State(pool): State<MySqlPool>, Json(order): Json<CreateOrder>) -> Result<(StatusCode, HeaderMap, Json<Value>), AppError> {
+    let order = insert_order(&pool, &order).await?;
+    Ok(created_response(&uri, order.id, order.version, OrderV2Dto::from(order)))
+}
+
+/// Update an existing order
+#[utoipa::path(
+    put,
+    path = "/api/v1/orders/{id}",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body = CreateOrder,
+    responses(
+        (status = 200, description = "Order updated", body = OrderResponse,
+            headers(("ETag" = String, description = "New version of the order"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(order): Json<CreateOrder>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = replace_order(&pool, id, &order, if_match(&headers).as_deref()).await?;
+    Ok((etag_header(order.version), json_response(OrderDto::from(order))))
+}
+
+/// Update an existing order, responding with nested address snapshots
+#[utoipa::path(
+    put,
+    path = "/api/v2/orders/{id}",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body = CreateOrder,
+    responses(
+        (status = 200, description = "Order updated", body = OrderV2Response,
+            headers(("ETag" = String, description = "New version of the order"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_order_v2(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(order): Json<CreateOrder>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = replace_order(&pool, id, &order, if_match(&headers).as_deref()).await?;
+    Ok((etag_header(order.version), json_response(OrderV2Dto::from(order))))
+}
+
+/// Partially update an order (JSON Merge Patch)
+#[utoipa::path(
+    patch,
+    path = "/api/v1/orders/{id}",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body(content = PatchOrder, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Order updated", body = OrderResponse,
+            headers(("ETag" = String, description = "New version of the order"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(patch): Json<PatchOrder>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = apply_order_patch(&pool, id, patch, if_match(&headers).as_deref()).await?;
+    Ok((etag_header(order.version), json_response(OrderDto::from(order))))
+}
+
+/// Partially update an order (JSON Merge Patch), responding with nested address snapshots
+#[utoipa::path(
+    patch,
+    path = "/api/v2/orders/{id}",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body(content = PatchOrder, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Order updated", body = OrderV2Response,
+            headers(("ETag" = String, description = "New version of the order"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_order_v2(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap, Json(patch): Json<PatchOrder>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = apply_order_patch(&pool, id, patch, if_match(&headers).as_deref()).await?;
+    Ok((etag_header(order.version), json_response(OrderV2Dto::from(order))))
+}
+
+/// Create many orders in one transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/orders/bulk-create",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<CreateOrder>,
+    responses(
+        (status = 201, description = "The created orders, in request order", body = OrderListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_create_orders(State(pool): State<MySqlPool>, Json(items): Json<Vec<CreateOrder>>) -> Result<(StatusCode, Json<Value>), AppError> {
+    let orders = insert_orders(&pool, &items).await?;
+    Ok((StatusCode::CREATED, json_response(orders.into_iter().map(OrderDto::from).collect::<Vec<_>>())))
+}
+
+/// Create many orders in one transaction, responding with nested address snapshots
+#[utoipa::path(
+    post,
+    path = "/api/v2/orders/bulk-create",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<CreateOrder>,
+    responses(
+        (status = 201, description = "The created orders, in request order", body = OrderV2ListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_create_orders_v2(State(pool): State<MySqlPool>, Json(items): Json<Vec<CreateOrder>>) -> Result<(StatusCode, Json<Value>), AppError> {
+    let orders = insert_orders(&pool, &items).await?;
+    Ok((StatusCode::CREATED, json_response(orders.into_iter().map(OrderV2Dto::from).collect::<Vec<_>>())))
+}
+
+/// Overwrite many orders in one transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/orders/bulk-update",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<BulkUpdateOrder>,
+    responses(
+        (status = 200, description = "The updated orders, in request order", body = OrderListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "An order changed since the given version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_update_orders(State(pool): State<MySqlPool>, Json(items): Json<Vec<BulkUpdateOrder>>) -> Result<Json<Value>, AppError> {
+    let orders = replace_orders(&pool, &items).await?;
+    Ok(json_response(orders.into_iter().map(OrderDto::from).collect::<Vec<_>>()))
+}
+
+/// Overwrite many orders in one transaction, responding with nested address snapshots
+#[utoipa::path(
+    post,
+    path = "/api/v2/orders/bulk-update",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<BulkUpdateOrder>,
+    responses(
+        (status = 200, description = "The updated orders, in request order", body = OrderV2ListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "An order changed since the given version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_update_orders_v2(State(pool): State<MySqlPool>, Json(items): Json<Vec<BulkUpdateOrder>>) -> Result<Json<Value>, AppError> {
+    let orders = replace_orders(&pool, &items).await?;
+    Ok(json_response(orders.into_iter().map(OrderV2Dto::from).collect::<Vec<_>>()))
+}
+
+/// Move an order to the trash
+#[utoipa::path(
+    delete,
+    path = "/api/v1/orders/{id}",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the deletion is based on"),
+    ),
+    responses(
+        (status = 200, description = "Order deleted", body = MessageResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap) -> Result<Json<Value>, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    soft_delete(&mut tx, "orders", "order", id, if_match(&headers).as_deref(), DeletionPolicy::Block).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    Ok(json_response("Order deleted successfully"))
+}
+
+/// Restore an order from the trash
+#[utoipa::path(
+    post,
+    path = "/api/v1/orders/{id}/restore",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the restore is based on"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    responses(
+        (status = 200, description = "Order restored", body = OrderResponse,
+            headers(("ETag" = String, description = "New version of the order"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The order is not deleted, or its customer or product is; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn restore_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = restore_order_row(&pool, id, if_match(&headers).as_deref()).await?;
+    Ok((etag_header(order.version), json_response(OrderDto::from(order))))
+}
+
+/// Restore an order from the trash, with nested address snapshots
+#[utoipa::path(
+    post,
+    path = "/api/v2/orders/{id}/restore",
+    tag = "orders",
+    params(
+        ("id" = i32, Path, description = "Order id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the restore is based on"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    responses(
+        (status = 200, description = "Order restored", body = OrderV2Response,
+            headers(("ETag" = String, description = "New version of the order"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The order is not deleted, or its customer or product is; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn restore_order_v2(Path(id): Path<i32>, State(pool): State<MySqlPool>, headers: HeaderMap) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let order = restore_order_row(&pool, id, if_match(&headers).as_deref()).await?;
+    Ok((etag_header(order.version), json_response(OrderV2Dto::from(order))))
+}
+
+/// Move multiple orders to the trash by IDs
+#[utoipa::path(
+    post,
+    path = "/api/v1/orders/bulk-delete",
+    tag = "orders",
+    params(
+        BulkDeleteParams,
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    request_body = Vec<i32>,
+    responses(
+        (status = 200, description = "Which ids were deleted, missing or still referenced", body = BulkDeleteResponse),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "With `atomic`: some ids do not exist, nothing was deleted", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "With `atomic`: some ids are still referenced, nothing was deleted; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_orders(State(pool): State<MySqlPool>, Query(params): Query<BulkDeleteParams>, Json(ids): Json<Vec<i32>>) -> Result<Json<Value>, AppError> {
+    let report = delete_by_ids(&pool, "orders", "order", &ids, params.atomic, DeletionPolicy::Block).await?;
+
+    info!(
+        resource = "orders",
+        requested = ids.len(),
+        deleted = report.deleted.len(),
+        missing = report.missing.len(),
+        blocked = report.blocked.len(),
+        "Bulk deleted orders"
+    );
+
+    Ok(json_response(report))
+}
+
+// Row access shared by every API version; the handlers only pick the response shape
+async fn fetch_orders(pool: &MySqlPool, trash: &TrashParams) -> Result<Vec<Order>, AppError> {
+    let orders = sqlx::query_as::<_, Order>(&format!("SELECT * FROM orders WHERE {}", trash.condition()))
+        .fetch_all(pool)
+        .timed("list_orders")
         .await
         .map_err(|e| {
-	    error!("Failed to fetch orders: {:?}", e);
+	    error!(resource = "orders", error = ?e, "Failed to fetch orders");
 	    AppError::DatabaseError(e)
 	})?;
-    info!("Successfully fetched {} orders", orders.len());
-    let headers = content_range_header("orders", orders.len());
-    Ok((headers, json_response(orders)))
+    info!(resource = "orders", count = orders.len(), "Successfully fetched orders");
+    Ok(orders)
 }
 
-/// Get a specific order by ID
-pub async fn get_order(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
-    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = ?")
+async fn fetch_order<'e>(executor: impl Executor<'e, Database = MySql>, id: i32) -> Result<Order, AppError> {
+    sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = ?")
         .bind(id)
-        .fetch_one(&pool)
+        .fetch_one(executor)
+        .timed("get_order")
         .await
-        .map_err(|_| AppError::NotFound)?;
+        .map_err(|e| map_fetch_error(e, "order", id))
+}
+
+// Take an order out of the trash; it may not point at a customer or product that is still there
+async fn restore_order_row(pool: &MySqlPool, id: i32, if_match: Option<&[String]>) -> Result<Order, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    restore(&mut tx, "orders", "order", id, if_match).await?;
 
-    Ok(json_response(order))
+    let (customer_deleted, product_deleted): (bool, bool) = sqlx::query_as(
+        "SELECT c.deleted_at IS NOT NULL, p.deleted_at IS NOT NULL FROM orders o \
+         JOIN customers c ON c.id = o.customer_id JOIN products p ON p.id = o.product_id WHERE o.id = ?",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .timed("restore_order")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    let deleted_parent = if customer_deleted { Some("customer") } else if product_deleted { Some("product") } else { None };
+    if let Some(parent) = deleted_parent {
+        return Err(AppError::Conflict {
+            message: format!("The order's {} is deleted; restore it first", parent),
+            fields: None,
+        });
+    }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    info!(resource = "orders", id, "Restored order");
+    fetch_order(pool, id).await
 }
 
-/// Create a new order
-pub async fn create_order(State(pool): State<MySqlPool>, Json(order): Json<CreateOrder>) -> Result<Json<Value>, AppError> {
+// Validate and store a new order, returning the stored row
+async fn insert_order(pool: &MySqlPool, order: &CreateOrder) -> Result<Order, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    let id = store_order(&mut tx, order).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    counter!("orders_created_total").increment(1);
+    fetch_order(pool, id).await
+}
+
+// Validate and store new orders in one transaction, returning the stored rows in request order
+async fn insert_orders(pool: &MySqlPool, orders: &[CreateOrder]) -> Result<Vec<Order>, AppError> {
+    // Validate every item before writing any of them
+    validate_items(orders.iter())?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    let mut ids = Vec::with_capacity(orders.len());
+    for (index, order) in orders.iter().enumerate() {
+        ids.push(store_order(&mut tx, order).await.map_err(|e| bulk_item_error(index, e))?);
+    }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    counter!("orders_created_total").increment(ids.len() as u64);
+    info!(resource = "orders", created = ids.len(), "Bulk created orders");
+    fetch_by_ids(pool, "orders", &ids).await
+}
+
+// Validate and overwrite an order, returning the stored row
+async fn replace_order(pool: &MySqlPool, id: i32, order: &CreateOrder, if_match: Option<&[String]>) -> Result<Order, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    overwrite_order(&mut tx, id, order, if_match).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    fetch_order(pool, id).await
+}
+
+// Validate and overwrite orders in one transaction, returning the stored rows in request order
+async fn replace_orders(pool: &MySqlPool, items: &[BulkUpdateOrder]) -> Result<Vec<Order>, AppError> {
+    // Validate every item before writing any of them
+    validate_items(items.iter())?;
+    validate_unique_ids(items.iter().map(|item| item.id))?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    for (index, item) in items.iter().enumerate() {
+        let if_match = item.version.map(|version| vec![etag(version)]);
+        overwrite_order(&mut tx, item.id, &item.order, if_match.as_deref())
+            .await
+            .map_err(|e| bulk_item_error(index, e))?;
+    }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    info!(resource = "orders", updated = items.len(), "Bulk updated orders");
+    let ids: Vec<i32> = items.iter().map(|item| item.id).collect();
+    fetch_by_ids(pool, "orders", &ids).await
+}
+
+// Insert an order inside the caller's transaction, returning its id
+async fn store_order(conn: &mut MySqlConnection, order: &CreateOrder) -> Result<i32, AppError> {
     // Validate the input
     order.validate().map_err(AppError::ValidationError)?;
 
@@ -44,26 +543,62 @@ pub async fn create_order(State(pool): State<MySqlPool>, Json(order): Json<Creat
     })?;
 
     // Check if the customer exists
-    validate_customer_exists(&pool, order.customer_id).await?;
+    validate_customer_exists(&mut *conn, order.customer_id).await?;
 
     // Check if the product exists
-    validate_product_exists(&pool, order.product_id).await?;
+    validate_product_exists(&mut *conn, order.product_id).await?;
+
+    // Copy the chosen (or default) addresses so later edits don't rewrite the order
+    let shipping = resolve_address(&mut *conn, order.customer_id, order.shipping_address_id, "shipping", true).await?;
+    let billing = resolve_address(&mut *conn, order.customer_id, order.billing_address_id, "billing", true).await?;
 
     // Insert the new order into the database
-    let _ = sqlx::query("INSERT INTO orders (customer_id, product_id, quantity, order_date) VALUES (?, ?, ?, ?)")
+    let result = sqlx::query(
+        "INSERT INTO orders (customer_id, product_id, quantity, order_date, \
+         shipping_street, shipping_city, shipping_postal_code, shipping_country_code, \
+         billing_street, billing_city, billing_postal_code, billing_country_code) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
         .bind(order.customer_id)
         .bind(order.product_id)
         .bind(order.quantity)
         .bind(order.order_date)
-        .execute(&pool)
+        .bind(shipping.as_ref().map(|a| &a.street))
+        .bind(shipping.as_ref().map(|a| &a.city))
+        .bind(shipping.as_ref().map(|a| &a.postal_code))
+        .bind(shipping.as_ref().map(|a| &a.country_code))
+        .bind(billing.as_ref().map(|a| &a.street))
+        .bind(billing.as_ref().map(|a| &a.city))
+        .bind(billing.as_ref().map(|a| &a.postal_code))
+        .bind(billing.as_ref().map(|a| &a.country_code))
+        .execute(&mut *conn)
+        .timed("create_order")
         .await
-        .map_err(AppError::DatabaseError)?;
+        .map_err(|e| map_constraint_violation(e, "order"))?;
 
-    Ok(json_response("Order created successfully"))
+    let id = inserted_id(&result);
+    audit::record(conn, AuditAction::Create, "orders", id, None).await?;
+    Ok(id)
 }
 
-/// Update an existing order
-pub async fn update_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, Json(order): Json<CreateOrder>) -> Result<Json<Value>, AppError> {
+// PUT keeping the stored address snapshot where no new address was chosen
+const UPDATE_ORDER: &str = "UPDATE orders SET customer_id = ?, product_id = ?, quantity = ?, order_date = ?, \
+     shipping_street = COALESCE(?, shipping_street), shipping_city = COALESCE(?, shipping_city), \
+     shipping_postal_code = COALESCE(?, shipping_postal_code), shipping_country_code = COALESCE(?, shipping_country_code), \
+     billing_street = COALESCE(?, billing_street), billing_city = COALESCE(?, billing_city), \
+     billing_postal_code = COALESCE(?, billing_postal_code), billing_country_code = COALESCE(?, billing_country_code), \
+     version = version + 1 \
+     WHERE id = ?";
+
+// PUT moving the order to another customer, whose addresses (or none) replace the snapshot
+const REPLACE_ORDER: &str = "UPDATE orders SET customer_id = ?, product_id = ?, quantity = ?, order_date = ?, \
+     shipping_street = ?, shipping_city = ?, shipping_postal_code = ?, shipping_country_code = ?, \
+     billing_street = ?, billing_city = ?, billing_postal_code = ?, billing_country_code = ?, \
+     version = version + 1 \
+     WHERE id = ?";
+
+// Overwrite an order inside the caller's transaction
+async fn overwrite_order(conn: &mut MySqlConnection, id: i32, order: &CreateOrder, if_match: Option<&[String]>) -> Result<(), AppError> {
     // Validate the input
     order.validate().map_err(AppError::ValidationError)?;
 
@@ -74,72 +609,173 @@ pub async fn update_order(Path(id): Path<i32>, State(pool): State<MySqlPool>, Js
         AppError::ValidationError(errors)
     })?;
 
+    // Check the order exists and still has the version the client edited
+    lock_version(&mut *conn, "orders", "order", id, if_match).await?;
+
     // Check if the customer exists
-    validate_customer_exists(&pool, order.customer_id).await?;
+    validate_customer_exists(&mut *conn, order.customer_id).await?;
 
     // Check if the product exists
-    validate_product_exists(&pool, order.product_id).await?;
+    validate_product_exists(&mut *conn, order.product_id).await?;
+
+    // A snapshot of another customer's addresses must not stay on the order: when the customer
+    // changes, the new one's chosen (or default) addresses replace it entirely
+    let current_customer: i32 = sqlx::query_scalar("SELECT customer_id FROM orders WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .timed("update_order")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    let customer_changed = current_customer != order.customer_id;
+
+    // Otherwise only re-copy addresses that were explicitly chosen, keeping the stored snapshot
+    let shipping = resolve_address(&mut *conn, order.customer_id, order.shipping_address_id, "shipping", customer_changed).await?;
+    let billing = resolve_address(&mut *conn, order.customer_id, order.billing_address_id, "billing", customer_changed).await?;
 
     // Update the order in the database
-    let _ = sqlx::query("UPDATE orders SET customer_id = ?, product_id = ?, quantity = ?, order_date = ? WHERE id = ?")
+    let before = audit::snapshot(&mut *conn, "orders", id).await?;
+    sqlx::query(if customer_changed { REPLACE_ORDER } else { UPDATE_ORDER })
         .bind(order.customer_id)
         .bind(order.product_id)
         .bind(order.quantity)
         .bind(order.order_date)
+        .bind(shipping.as_ref().map(|a| &a.street))
+        .bind(shipping.as_ref().map(|a| &a.city))
+        .bind(shipping.as_ref().map(|a| &a.postal_code))
+        .bind(shipping.as_ref().map(|a| &a.country_code))
+        .bind(billing.as_ref().map(|a| &a.street))
+        .bind(billing.as_ref().map(|a| &a.city))
+        .bind(billing.as_ref().map(|a| &a.postal_code))
+        .bind(billing.as_ref().map(|a| &a.country_code))
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *conn)
+        .timed("update_order")
         .await
-        .map_err(AppError::DatabaseError)?;
-
-    Ok(json_response("Order updated successfully"))
+        .map_err(|e| map_constraint_violation(e, "order"))?;
+    audit::record(conn, AuditAction::Update, "orders", id, before).await
 }
 
-/// Delete an order by ID
-pub async fn delete_order(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
-    // Delete the order from the database
-    let result = sqlx::query("DELETE FROM orders WHERE id = ?")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+// Validate and apply a merge patch to an order, returning the stored row
+async fn apply_order_patch(pool: &MySqlPool, id: i32, patch: PatchOrder, if_match: Option<&[String]>) -> Result<Order, AppError> {
+    // Validate only the supplied fields
+    validate_patch(&patch, &patch.cleared_required())?;
 
-    // Check if the order was actually deleted
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
 
-    Ok(json_response("Order deleted successfully"))
-}
+    // Check the order still has the version the client edited, locking it so it cannot
+    // change between the reads below and the update
+    lock_version(&mut tx, "orders", "order", id, if_match).await?;
 
-/// Delete multiple orders by IDs
-pub async fn delete_orders(State(pool): State<MySqlPool>, Json(ids): Json<Vec<i32>>) -> Result<Json<Value>, AppError> {
-    if ids.is_empty() {
-        let mut errors = ValidationErrors::new();
-        errors.add("ids", ValidationError::new("No IDs provided"));
-        return Err(AppError::ValidationError(errors));
+    // The stored order supplies the customer when only an address changes
+    let existing = fetch_order(&mut *tx, id).await?;
+
+    if let Some(Some(customer_id)) = patch.customer_id {
+        validate_customer_exists(&mut *tx, customer_id).await?;
+    }
+    if let Some(Some(product_id)) = patch.product_id {
+        validate_product_exists(&mut *tx, product_id).await?;
     }
+    let customer_id = patch.customer_id.flatten().unwrap_or(existing.customer_id);
+    let customer_changed = customer_id != existing.customer_id;
 
-    // Construct the query with placeholders for each ID
-    let query = format!(
-        "DELETE FROM orders WHERE id IN ({})",
-        ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
-    );
+    // A supplied address id is copied onto the order, `null` removes the snapshot; moving the
+    // order to another customer replaces an untouched snapshot with their default address
+    let shipping = match patch.shipping_address_id {
+        Some(address_id) => Some(resolve_address(&mut *tx, customer_id, address_id, "shipping", false).await?),
+        None if customer_changed => Some(resolve_address(&mut *tx, customer_id, None, "shipping", true).await?),
+        None => None,
+    };
+    let billing = match patch.billing_address_id {
+        Some(address_id) => Some(resolve_address(&mut *tx, customer_id, address_id, "billing", false).await?),
+        None if customer_changed => Some(resolve_address(&mut *tx, customer_id, None, "billing", true).await?),
+        None => None,
+    };
+
+    // Update only the supplied columns
+    let mut query = QueryBuilder::<MySql>::new("UPDATE orders SET ");
+    let mut set = query.separated(", ");
+    let changed = [
+        push_patch_field(&mut set, "customer_id", patch.customer_id),
+        push_patch_field(&mut set, "product_id", patch.product_id),
+        push_patch_field(&mut set, "quantity", patch.quantity),
+        push_patch_field(&mut set, "order_date", patch.order_date),
+        push_address_snapshot(&mut set, "shipping", shipping),
+        push_address_snapshot(&mut set, "billing", billing),
+    ]
+    .contains(&true);
 
-    // Execute the query
-    let mut query = sqlx::query(&query);
-    for id in ids.iter() {
-        query = query.bind(id);
+    if changed {
+        let before = audit::snapshot(&mut tx, "orders", id).await?;
+        set.push("version = version + 1");
+        query.push(" WHERE id = ").push_bind(id);
+        query
+            .build()
+            .execute(&mut *tx)
+            .timed("patch_order")
+            .await
+            .map_err(|e| map_constraint_violation(e, "order"))?;
+        audit::record(&mut tx, AuditAction::Update, "orders", id, before).await?;
     }
 
-    let result = query
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+
+    fetch_order(pool, id).await
+}
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
+// Look up the address to copy onto an order, optionally falling back to the customer's default
+async fn resolve_address<'e>(
+    executor: impl Executor<'e, Database = MySql>,
+    customer_id: i32,
+    address_id: Option<i32>,
+    address_type: &str,
+    use_default: bool,
+) -> Result<Option<CustomerAddress>, AppError> {
+    match address_id {
+        Some(address_id) => {
+            let address = sqlx::query_as::<_, CustomerAddress>(
+                "SELECT * FROM customer_addresses WHERE id = ? AND customer_id = ? AND address_type = ?"
+            )
+                .bind(address_id)
+                .bind(customer_id)
+                .bind(address_type)
+                .fetch_optional(executor)
+                .timed("resolve_address")
+                .await
+                .map_err(AppError::DatabaseError)?;
+
+            address.map(Some).ok_or_else(|| AppError::not_found("address", address_id))
+        }
+        None if use_default => {
+            sqlx::query_as::<_, CustomerAddress>(
+                "SELECT * FROM customer_addresses WHERE customer_id = ? AND address_type = ? AND is_default = TRUE LIMIT 1"
+            )
+                .bind(customer_id)
+                .bind(address_type)
+                .fetch_optional(executor)
+                .timed("resolve_address")
+                .await
+                .map_err(AppError::DatabaseError)
+        }
+        None => Ok(None),
     }
+}
+
+// Set or clear the `<prefix>_*` snapshot columns when a patch touches that address
+fn push_address_snapshot(
+    set: &mut Separated<'_, '_, MySql, &'static str>,
+    prefix: &str,
+    address: Option<Option<CustomerAddress>>,
+) -> bool {
+    let Some(address) = address else {
+        return false;
+    };
 
-    // Return the deleted IDs in the `data` field
-    Ok(json_response(ids))
+    let (street, city, postal_code, country_code) = match address {
+        Some(a) => (Some(a.street), Some(a.city), Some(a.postal_code), Some(a.country_code)),
+        None => (None, None, None, None),
+    };
+    push_patch_field(set, &format!("{}_street", prefix), Some(street));
+    push_patch_field(set, &format!("{}_city", prefix), Some(city));
+    push_patch_field(set, &format!("{}_postal_code", prefix), Some(postal_code));
+    push_patch_field(set, &format!("{}_country_code", prefix), Some(country_code))
 }
@@ -1,121 +1,430 @@
-use axum::{Json, extract::{State, Path}, http::HeaderMap};
+use axum::{Json, extract::{State, Path, OriginalUri, Query}, http::{HeaderMap, StatusCode}, response::Response};
 use serde_json::Value;
-use sqlx::MySqlPool;
-use crate::models::product::{Product, CreateProduct};
-use crate::utils::{AppError, json_response, content_range_header};
+use sqlx::{MySql, MySqlConnection, MySqlPool, QueryBuilder};
+use std::sync::Arc;
+use crate::models::{BulkDeleteParams, TrashParams};
+use crate::models::product::{Product, CreateProduct, BulkUpdateProduct, PatchProduct};
+use crate::audit::{self, AuditAction};
+use crate::cache::{catalogue_etag, ProductCache};
+use crate::config::DeletionConfig;
+use crate::dto::v1::ProductDto;
+use crate::utils::{AppError, json_response, content_range_header, map_fetch_error, validate_patch, push_patch_field, created_response, conditional_response, etag, etag_header, if_match, lock_version, soft_delete, restore, delete_by_ids, validate_items, validate_unique_ids, bulk_item_error, fetch_by_ids, inserted_id};
+use crate::metrics::TimedQuery;
 use validator::Validate;
 use tracing::{info, error};
-use validator::{ValidationErrors, ValidationError};
 
 /// List all products
-pub async fn list_products(State(pool): State<MySqlPool>) -> Result<(HeaderMap, Json<Value>), AppError> {
-    info!("Fetching products from the database");
+#[utoipa::path(
+    get,
+    path = "/api/v1/products",
+    tag = "products",
+    params(
+        TrashParams,
+        ("If-None-Match" = Option<String>, Header, description = "ETag of the list the client already has"),
+    ),
+    responses(
+        (status = 200, description = "All products", body = ProductListResponse,
+            headers(("ETag" = String, description = "Weak tag covering every product's version"), ("Cache-Control" = String))),
+        (status = 304, description = "The client's copy is current"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn list_products(State(pool): State<MySqlPool>, State(cache): State<ProductCache>, Query(trash): Query<TrashParams>, headers: HeaderMap) -> Result<Response, AppError> {
+    info!(resource = "products", "Fetching products");
 
-    let products = sqlx::query_as::<_, Product>("SELECT * FROM products")
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to fetch products: {:?}", e);
-            AppError::DatabaseError(e)
-        })?;
-
-    info!("Successfully fetched {} products", products.len());
-    let headers = content_range_header("products", products.len());
-    Ok((headers, json_response(products)))
+    // Only the live catalogue is cached; the trash views go to the database
+    let products = if trash.live_only() {
+        cache.products(&pool).await
+    } else {
+        sqlx::query_as::<_, Product>(&format!("SELECT * FROM products WHERE {} ORDER BY id", trash.condition()))
+            .fetch_all(&pool)
+            .timed("list_products")
+            .await
+            .map(Arc::new)
+    }
+    .map_err(|e| {
+        error!(resource = "products", error = ?e, "Failed to fetch products");
+        AppError::DatabaseError(e)
+    })?;
+
+    info!(resource = "products", count = products.len(), "Successfully fetched products");
+
+    // No `Last-Modified` here: deleting a product leaves no newer timestamp behind,
+    // so only the entity tag notices it
+    let range = content_range_header("products", products.len());
+    Ok(conditional_response(&headers, range, &catalogue_etag(&products), None, cache.cache_control(), || {
+        products.iter().cloned().map(ProductDto::from).collect::<Vec<_>>()
+    }))
 }
 
 /// Get a specific product by ID
-pub async fn get_product(Path(id): Path<u32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
-    let product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ?")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| AppError::NotFound)?;
+#[utoipa::path(
+    get,
+    path = "/api/v1/products/{id}",
+    tag = "products",
+    params(
+        ("id" = i32, Path, description = "Product id"),
+        TrashParams,
+        ("If-None-Match" = Option<String>, Header, description = "ETag of the product the client already has"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Used when no If-None-Match is sent"),
+    ),
+    responses(
+        (status = 200, description = "The product", body = ProductResponse,
+            headers(("ETag" = String, description = "Current version of the product"), ("Last-Modified" = String), ("Cache-Control" = String))),
+        (status = 304, description = "The client's copy is current"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_product(Path(id): Path<i32>, State(pool): State<MySqlPool>, State(cache): State<ProductCache>, Query(trash): Query<TrashParams>, headers: HeaderMap) -> Result<Response, AppError> {
+    let product = if trash.live_only() {
+        cache.product(&pool, id).await
+    } else {
+        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&pool)
+            .timed("get_product")
+            .await
+    }
+    .map_err(AppError::DatabaseError)?
+    .filter(|product| trash.shows(product.deleted_at))
+    .ok_or_else(|| AppError::not_found("product", id))?;
 
-    Ok(json_response(product))
+    Ok(conditional_response(&headers, HeaderMap::new(), &etag(product.version), Some(product.updated_at), cache.cache_control(), || {
+        ProductDto::from(product)
+    }))
 }
 
 /// Create a new product
-pub async fn create_product(State(pool): State<MySqlPool>, Json(product): Json<CreateProduct>) -> Result<Json<Value>, AppError> {
+#[utoipa::path(
+    post,
+    path = "/api/v1/products",
+    tag = "products",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = CreateProduct,
+    responses(
+        (status = 201, description = "Product created", body = ProductResponse,
+            headers(("Location" = String, description = "URL of the new product"), ("ETag" = String, description = "Version of the new product"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_product(OriginalUri(uri): OriginalUri, State(pool): State<MySqlPool>, State(cache): State<ProductCache>, Json(product): Json<CreateProduct>) -> Result<(StatusCode, HeaderMap, Json<Value>), AppError> {
     // Validate the input
     product.validate().map_err(AppError::ValidationError)?;
 
     // Insert the new product into the database
-    let _ = sqlx::query("INSERT INTO products (name, description, price, in_stock) VALUES (?, ?, ?, ?)")
-        .bind(&product.name)
-        .bind(&product.description) // Handle Option<String> properly
-        .bind(product.price)
-        .bind(product.in_stock)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    let id = insert_product(&mut tx, &product).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
 
-    Ok(json_response("Product created successfully"))
+    let product = fetch_product(&pool, id).await?;
+    Ok(created_response(&uri, product.id, product.version, ProductDto::from(product)))
 }
 
 /// Update an existing product
-pub async fn update_product(Path(id): Path<u32>, State(pool): State<MySqlPool>, Json(product): Json<CreateProduct>) -> Result<Json<Value>, AppError> {
+#[utoipa::path(
+    put,
+    path = "/api/v1/products/{id}",
+    tag = "products",
+    params(
+        ("id" = i32, Path, description = "Product id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body = CreateProduct,
+    responses(
+        (status = 200, description = "Product updated", body = ProductResponse,
+            headers(("ETag" = String, description = "New version of the product"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_product(Path(id): Path<i32>, State(pool): State<MySqlPool>, State(cache): State<ProductCache>, headers: HeaderMap, Json(product): Json<CreateProduct>) -> Result<(HeaderMap, Json<Value>), AppError> {
     // Validate the input
     product.validate().map_err(AppError::ValidationError)?;
 
-    let _ = sqlx::query("UPDATE products SET name = ?, description = ?,   price = ?, in_stock = ? WHERE id = ?")
-        .bind(&product.name)
-	.bind(&product.description)
-        .bind(product.price)
-        .bind(product.in_stock)
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    replace_product(&mut tx, id, &product, if_match(&headers).as_deref()).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
 
-    Ok(json_response("Product updated successfully"))
+    let product = fetch_product(&pool, id).await?;
+    Ok((etag_header(product.version), json_response(ProductDto::from(product))))
 }
 
-/// Delete a product by ID
-pub async fn delete_product(Path(id): Path<i32>, State(pool): State<MySqlPool>) -> Result<Json<Value>, AppError> {
-     let result = sqlx::query("DELETE FROM products WHERE id = ?")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(AppError::DatabaseError)?;
+/// Partially update a product (JSON Merge Patch)
+#[utoipa::path(
+    patch,
+    path = "/api/v1/products/{id}",
+    tag = "products",
+    params(
+        ("id" = i32, Path, description = "Product id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the update is based on"),
+    ),
+    request_body(content = PatchProduct, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Product updated", body = ProductResponse,
+            headers(("ETag" = String, description = "New version of the product"))),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_product(Path(id): Path<i32>, State(pool): State<MySqlPool>, State(cache): State<ProductCache>, headers: HeaderMap, Json(patch): Json<PatchProduct>) -> Result<(HeaderMap, Json<Value>), AppError> {
+    // Validate only the supplied fields
+    validate_patch(&patch, &patch.cleared_required())?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    // Check the product exists and still has the version the client edited
+    lock_version(&mut tx, "products", "product", id, if_match(&headers).as_deref()).await?;
+
+    // Update only the supplied columns
+    let mut query = QueryBuilder::<MySql>::new("UPDATE products SET ");
+    let mut set = query.separated(", ");
+    let changed = [
+        push_patch_field(&mut set, "name", patch.name),
+        push_patch_field(&mut set, "description", patch.description),
+        push_patch_field(&mut set, "price", patch.price),
+        push_patch_field(&mut set, "in_stock", patch.in_stock),
+    ]
+    .contains(&true);
+
+    if changed {
+        let before = audit::snapshot(&mut tx, "products", id).await?;
+        set.push("version = version + 1");
+        query.push(" WHERE id = ").push_bind(id);
+        query
+            .build()
+            .execute(&mut *tx)
+            .timed("patch_product")
+            .await
+            .map_err(AppError::DatabaseError)?;
+        audit::record(&mut tx, AuditAction::Update, "products", id, before).await?;
+    }
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
+
+    let product = fetch_product(&pool, id).await?;
+    Ok((etag_header(product.version), json_response(ProductDto::from(product))))
+}
+
+/// Create many products in one transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/products/bulk-create",
+    tag = "products",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<CreateProduct>,
+    responses(
+        (status = 201, description = "The created products, in request order", body = ProductListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_create_products(State(pool): State<MySqlPool>, State(cache): State<ProductCache>, Json(items): Json<Vec<CreateProduct>>) -> Result<(StatusCode, Json<Value>), AppError> {
+    // Validate every item before writing any of them
+    validate_items(items.iter())?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    let mut ids = Vec::with_capacity(items.len());
+    for (index, product) in items.iter().enumerate() {
+        ids.push(insert_product(&mut tx, product).await.map_err(|e| bulk_item_error(index, e))?);
+    }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
+
+    info!(resource = "products", created = ids.len(), "Bulk created products");
+
+    let products = fetch_by_ids::<Product>(&pool, "products", &ids).await?;
+    Ok((StatusCode::CREATED, json_response(products.into_iter().map(ProductDto::from).collect::<Vec<_>>())))
+}
 
-    // Check if the product was actually deleted
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
+/// Overwrite many products in one transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/products/bulk-update",
+    tag = "products",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response")),
+    request_body = Vec<BulkUpdateProduct>,
+    responses(
+        (status = 200, description = "The updated products, in request order", body = ProductListResponse),
+        (status = 400, description = "Validation error, reported per item under `items.<index>`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "A product changed since the given version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn bulk_update_products(State(pool): State<MySqlPool>, State(cache): State<ProductCache>, Json(items): Json<Vec<BulkUpdateProduct>>) -> Result<Json<Value>, AppError> {
+    // Validate every item before writing any of them
+    validate_items(items.iter())?;
+    validate_unique_ids(items.iter().map(|item| item.id))?;
+
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    for (index, item) in items.iter().enumerate() {
+        let if_match = item.version.map(|version| vec![etag(version)]);
+        replace_product(&mut tx, item.id, &item.product, if_match.as_deref())
+            .await
+            .map_err(|e| bulk_item_error(index, e))?;
     }
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
+
+    info!(resource = "products", updated = items.len(), "Bulk updated products");
+
+    let ids: Vec<i32> = items.iter().map(|item| item.id).collect();
+    let products = fetch_by_ids::<Product>(&pool, "products", &ids).await?;
+    Ok(json_response(products.into_iter().map(ProductDto::from).collect::<Vec<_>>()))
+}
+
+/// Move a product to the trash
+#[utoipa::path(
+    delete,
+    path = "/api/v1/products/{id}",
+    tag = "products",
+    params(
+        ("id" = i32, Path, description = "Product id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the deletion is based on"),
+    ),
+    responses(
+        (status = 200, description = "Product deleted", body = MessageResponse),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Still referenced by live orders, which are listed (under the `block` policy)", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_product(Path(id): Path<i32>, State(pool): State<MySqlPool>, State(cache): State<ProductCache>, State(deletion): State<DeletionConfig>, headers: HeaderMap) -> Result<Json<Value>, AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    soft_delete(&mut tx, "products", "product", id, if_match(&headers).as_deref(), deletion.product_orders).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
 
     Ok(json_response("Product deleted successfully"))
 }
 
-/// Delete multiple products by IDs
-pub async fn delete_products(State(pool): State<MySqlPool>, Json(ids): Json<Vec<i32>>) -> Result<Json<Value>, AppError> {
-    if ids.is_empty() {
-        let mut errors = ValidationErrors::new();
-        errors.add("ids", ValidationError::new("No IDs provided"));
-        return Err(AppError::ValidationError(errors));
+/// Restore a product from the trash
+#[utoipa::path(
+    post,
+    path = "/api/v1/products/{id}/restore",
+    tag = "products",
+    params(
+        ("id" = i32, Path, description = "Product id"),
+        ("If-Match" = Option<String>, Header, description = "ETag the restore is based on"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    responses(
+        (status = 200, description = "Product restored", body = ProductResponse,
+            headers(("ETag" = String, description = "New version of the product"))),
+        (status = 404, description = "Not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The product is not deleted; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Changed since the given ETag", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn restore_product(Path(id): Path<i32>, State(pool): State<MySqlPool>, State(cache): State<ProductCache>, headers: HeaderMap) -> Result<(HeaderMap, Json<Value>), AppError> {
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+    restore(&mut tx, "products", "product", id, if_match(&headers).as_deref()).await?;
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    cache.invalidate();
+
+    info!(resource = "products", id, "Restored product");
+
+    let product = fetch_product(&pool, id).await?;
+    Ok((etag_header(product.version), json_response(ProductDto::from(product))))
+}
+
+/// Move multiple products to the trash by IDs
+#[utoipa::path(
+    post,
+    path = "/api/v1/products/bulk-delete",
+    tag = "products",
+    params(
+        BulkDeleteParams,
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key; retries with it replay the first response"),
+    ),
+    request_body = Vec<i32>,
+    responses(
+        (status = 200, description = "Which ids were deleted, missing or still referenced", body = BulkDeleteResponse),
+        (status = 400, description = "Validation error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "With `atomic`: some ids do not exist, nothing was deleted", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "With `atomic`: some ids are still referenced, nothing was deleted; or same Idempotency-Key still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Idempotency-Key reused with a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Database error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_products(State(pool): State<MySqlPool>, State(cache): State<ProductCache>, State(deletion): State<DeletionConfig>, Query(params): Query<BulkDeleteParams>, Json(ids): Json<Vec<i32>>) -> Result<Json<Value>, AppError> {
+    let report = delete_by_ids(&pool, "products", "product", &ids, params.atomic, deletion.product_orders).await?;
+    if !report.deleted.is_empty() {
+        cache.invalidate();
     }
 
-    // Construct the query with placeholders for each ID
-    let query = format!(
-        "DELETE FROM products WHERE id IN ({})",
-        ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+    info!(
+        resource = "products",
+        requested = ids.len(),
+        deleted = report.deleted.len(),
+        missing = report.missing.len(),
+        blocked = report.blocked.len(),
+        "Bulk deleted products"
     );
 
-    // Execute the query
-    let mut query = sqlx::query(&query);
-    for id in ids.iter() {
-        query = query.bind(id);
-    }
+    Ok(json_response(report))
+}
+
+// Load a product for the response body, answering 404 when it does not exist
+async fn fetch_product(pool: &MySqlPool, id: i32) -> Result<Product, AppError> {
+    sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .timed("get_product")
+        .await
+        .map_err(|e| map_fetch_error(e, "product", id))
+}
 
-    let result = query
-        .execute(&pool)
+// Store a new product inside the caller's transaction, returning its id
+async fn insert_product(conn: &mut MySqlConnection, product: &CreateProduct) -> Result<i32, AppError> {
+    let result = sqlx::query("INSERT INTO products (name, description, price, in_stock) VALUES (?, ?, ?, ?)")
+        .bind(&product.name)
+        .bind(&product.description) // Handle Option<String> properly
+        .bind(product.price)
+        .bind(product.in_stock)
+        .execute(&mut *conn)
+        .timed("create_product")
         .await
         .map_err(AppError::DatabaseError)?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
+    let id = inserted_id(&result);
+    audit::record(conn, AuditAction::Create, "products", id, None).await?;
+    Ok(id)
+}
+
+// Overwrite a product inside the caller's transaction
+async fn replace_product(conn: &mut MySqlConnection, id: i32, product: &CreateProduct, if_match: Option<&[String]>) -> Result<(), AppError> {
+    // Check the product exists and still has the version the client edited
+    lock_version(&mut *conn, "products", "product", id, if_match).await?;
 
-    // Return the deleted IDs in the `data` field
-    Ok(json_response(ids))
+    let before = audit::snapshot(&mut *conn, "products", id).await?;
+    sqlx::query("UPDATE products SET name = ?, description = ?, price = ?, in_stock = ?, version = version + 1 WHERE id = ?")
+        .bind(&product.name)
+        .bind(&product.description)
+        .bind(product.price)
+        .bind(product.in_stock)
+        .bind(id)
+        .execute(&mut *conn)
+        .timed("update_product")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    audit::record(conn, AuditAction::Update, "products", id, before).await
 }
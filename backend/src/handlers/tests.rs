@@ -0,0 +1,374 @@
+// Handler tests against a real database: every `#[sqlx::test]` runs on a fresh, migrated
+// database created on the server at DATABASE_URL, so they are ignored by default.
+// Run them with `cargo test -- --include-ignored`.
+use axum::{body::Body, http::{HeaderMap, Method, Request, StatusCode}, Router};
+use serde_json::{json, Value};
+use sqlx::MySqlPool;
+use tower::ServiceExt;
+use crate::cache::ProductCache;
+use crate::config::{Config, DeletionConfig, DeletionPolicy};
+use crate::handlers::ApiState;
+use crate::routes;
+
+// The API as mounted by `main`, with the default configuration
+fn app(pool: MySqlPool) -> Router {
+    app_with_deletion(pool, DeletionConfig::default())
+}
+
+fn app_with_deletion(pool: MySqlPool, deletion: DeletionConfig) -> Router {
+    let config = Config::default();
+    let state = ApiState {
+        pool,
+        product_cache: ProductCache::new(&config.cache, config.features.product_cache),
+        deletion,
+    };
+    Router::new().nest("/api", routes::create_api_routes(state, &config.api, &config.features))
+}
+
+// Sends one request and returns the status, headers and JSON body (`null` when empty)
+async fn send(app: &Router, method: Method, uri: &str, headers: &[(&str, &str)], body: Option<Value>) -> (StatusCode, HeaderMap, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = match body {
+        Some(body) => request.header("content-type", "application/json").body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .expect("valid request");
+
+    let response = app.clone().oneshot(request).await.expect("infallible router");
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.expect("readable body");
+    let body = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).expect("JSON body") };
+    (status, headers, body)
+}
+
+async fn insert_product(pool: &MySqlPool, name: &str, description: Option<&str>) -> i32 {
+    sqlx::query("INSERT INTO products (name, description, price, in_stock) VALUES (?, ?, 4.99, TRUE)")
+        .bind(name)
+        .bind(description)
+        .execute(pool)
+        .await
+        .expect("product fixture")
+        .last_insert_id() as i32
+}
+
+async fn insert_customer(pool: &MySqlPool, name: &str, email: &str) -> i32 {
+    sqlx::query("INSERT INTO customers (name, email, address) VALUES (?, ?, '1 Mill Lane')")
+        .bind(name)
+        .bind(email)
+        .execute(pool)
+        .await
+        .expect("customer fixture")
+        .last_insert_id() as i32
+}
+
+async fn insert_order(pool: &MySqlPool, customer_id: i32, product_id: i32) -> i32 {
+    sqlx::query(
+        "INSERT INTO orders (customer_id, product_id, quantity, order_date, shipping_street, shipping_city, shipping_postal_code, shipping_country_code) \
+         VALUES (?, ?, 2, '2025-03-01', '1 Mill Lane', 'Bristol', 'BS1 4DJ', 'GB')",
+    )
+    .bind(customer_id)
+    .bind(product_id)
+    .execute(pool)
+    .await
+    .expect("order fixture")
+    .last_insert_id() as i32
+}
+
+async fn count(pool: &MySqlPool, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn patch_clears_null_fields_and_keeps_omitted_ones(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let id = insert_product(&pool, "Rye bread", Some("Sourdough")).await;
+
+    let (status, _, _) = send(&app, Method::PATCH, &format!("/api/v1/products/{}", id), &[], Some(json!({ "description": null }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (name, description, in_stock): (String, Option<String>, bool) =
+        sqlx::query_as("SELECT name, description, in_stock FROM products WHERE id = ?")
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(name, "Rye bread");
+    assert_eq!(description, None);
+    assert!(in_stock);
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn patch_rejects_null_for_a_required_field(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let id = insert_product(&pool, "Rye bread", Some("Sourdough")).await;
+
+    let (status, _, body) = send(&app, Method::PATCH, &format!("/api/v1/products/{}", id), &[], Some(json!({ "name": null, "description": "Wholegrain" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["fields"]["name"].is_array(), "unexpected body {}", body);
+
+    // Nothing is written when any supplied field is invalid
+    let description: Option<String> = sqlx::query_scalar("SELECT description FROM products WHERE id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(description.as_deref(), Some("Sourdough"));
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn writes_with_a_stale_if_match_fail_with_412(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let id = insert_product(&pool, "Rye bread", Some("Sourdough")).await;
+    let uri = format!("/api/v1/products/{}", id);
+
+    // The first edit is based on the current version and bumps it
+    let (status, headers, _) = send(&app, Method::PATCH, &uri, &[("if-match", "\"1\"")], Some(json!({ "in_stock": false }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["etag"], "\"2\"");
+
+    // Every later write still based on version 1 is refused and changes nothing
+    let product = json!({ "name": "Spelt bread", "description": null, "price": "5.49", "in_stock": true });
+    for (method, body) in [
+        (Method::PUT, Some(product)),
+        (Method::PATCH, Some(json!({ "name": "Spelt bread" }))),
+        (Method::DELETE, None),
+    ] {
+        let (status, _, body) = send(&app, method.clone(), &uri, &[("if-match", "\"1\"")], body).await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED, "{} answered {}", method, body);
+    }
+
+    let (name, version): (String, i32) = sqlx::query_as("SELECT name, version FROM products WHERE id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((name.as_str(), version), ("Rye bread", 2));
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn reads_answer_304_when_the_client_copy_is_current(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let id = insert_product(&pool, "Rye bread", Some("Sourdough")).await;
+    let uri = format!("/api/v1/products/{}", id);
+
+    let (status, headers, _) = send(&app, Method::GET, &uri, &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    let etag = headers["etag"].to_str().unwrap().to_string();
+
+    let (status, headers, body) = send(&app, Method::GET, &uri, &[("if-none-match", &etag)], None).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(headers["etag"], etag.as_str());
+    assert_eq!(body, Value::Null);
+
+    // Once the product changes, the old tag gets the full record again
+    let (status, _, _) = send(&app, Method::PATCH, &uri, &[], Some(json!({ "in_stock": false }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, headers, body) = send(&app, Method::GET, &uri, &[("if-none-match", &etag)], None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(headers["etag"], etag.as_str());
+    assert_eq!(body["data"]["in_stock"], json!(false));
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn bulk_create_names_the_failing_item_and_writes_nothing(pool: MySqlPool) {
+    let app = app(pool.clone());
+    insert_customer(&pool, "Ada", "ada@example.com").await;
+
+    let items = json!([
+        { "name": "Grace", "email": "grace@example.com", "address": "2 Mill Lane" },
+        { "name": "Ada again", "email": "ADA@example.com", "address": "3 Mill Lane" },
+        { "name": "Alan", "email": "alan@example.com", "address": "4 Mill Lane" },
+    ]);
+    let (status, _, body) = send(&app, Method::POST, "/api/v1/customers/bulk-create", &[], Some(items)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["detail"].as_str().unwrap().starts_with("Item 1: "), "unexpected body {}", body);
+    assert!(body["fields"]["items"]["1"]["email"].is_array(), "unexpected body {}", body);
+
+    // The item written before the failure was rolled back with the rest
+    assert_eq!(count(&pool, "customers").await, 1);
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn bulk_update_is_all_or_nothing(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let rye = insert_product(&pool, "Rye bread", None).await;
+    let spelt = insert_product(&pool, "Spelt bread", None).await;
+
+    let items = json!([
+        { "id": rye, "version": 1, "name": "Dark rye bread", "description": null, "price": "3.80", "in_stock": true },
+        { "id": spelt, "version": 2, "name": "Light spelt bread", "description": null, "price": "4.20", "in_stock": true },
+    ]);
+    let (status, _, body) = send(&app, Method::POST, "/api/v1/products/bulk-update", &[], Some(items)).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED, "unexpected body {}", body);
+    assert_eq!(body["id"], json!(spelt.to_string()));
+
+    let names: Vec<String> = sqlx::query_scalar("SELECT name FROM products ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(names, ["Rye bread", "Spelt bread"]);
+}
+
+// Which of the given rows are in the trash
+async fn trashed(pool: &MySqlPool, table: &str, ids: &[i32]) -> Vec<bool> {
+    let mut trashed = Vec::with_capacity(ids.len());
+    for id in ids {
+        let deleted: bool = sqlx::query_scalar(&format!("SELECT deleted_at IS NOT NULL FROM {} WHERE id = ?", table))
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        trashed.push(deleted);
+    }
+    trashed
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn block_policy_refuses_to_delete_a_customer_with_live_orders(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let customer = insert_customer(&pool, "Ada", "ada@example.com").await;
+    let product = insert_product(&pool, "Rye bread", None).await;
+    let order = insert_order(&pool, customer, product).await;
+
+    let (status, _, body) = send(&app, Method::DELETE, &format!("/api/v1/customers/{}", customer), &[], None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["detail"], json!(format!("The customer is still referenced by orders {}", order)));
+    assert_eq!(trashed(&pool, "customers", &[customer]).await, [false]);
+    assert_eq!(trashed(&pool, "orders", &[order]).await, [false]);
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn cascade_policy_trashes_the_live_orders_too(pool: MySqlPool) {
+    let deletion = DeletionConfig { customer_orders: DeletionPolicy::Block, product_orders: DeletionPolicy::Cascade };
+    let app = app_with_deletion(pool.clone(), deletion);
+    let ada = insert_customer(&pool, "Ada", "ada@example.com").await;
+    let grace = insert_customer(&pool, "Grace", "grace@example.com").await;
+    let product = insert_product(&pool, "Rye bread", None).await;
+    let orders = [insert_order(&pool, ada, product).await, insert_order(&pool, grace, product).await];
+
+    let (status, _, body) = send(&app, Method::DELETE, &format!("/api/v1/products/{}", product), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "unexpected body {}", body);
+    assert_eq!(trashed(&pool, "products", &[product]).await, [true]);
+    assert_eq!(trashed(&pool, "orders", &orders).await, [true, true]);
+    assert_eq!(trashed(&pool, "customers", &[ada, grace]).await, [false, false]);
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn anonymise_policy_scrubs_the_customer_and_keeps_the_orders(pool: MySqlPool) {
+    let deletion = DeletionConfig { customer_orders: DeletionPolicy::Anonymise, product_orders: DeletionPolicy::Block };
+    let app = app_with_deletion(pool.clone(), deletion);
+    let customer = insert_customer(&pool, "Ada", "ada@example.com").await;
+    let product = insert_product(&pool, "Rye bread", None).await;
+    let order = insert_order(&pool, customer, product).await;
+
+    let (status, _, body) = send(&app, Method::DELETE, &format!("/api/v1/customers/{}", customer), &[], None).await;
+    assert_eq!(status, StatusCode::OK, "unexpected body {}", body);
+    assert_eq!(trashed(&pool, "customers", &[customer]).await, [true]);
+    assert_eq!(trashed(&pool, "orders", &[order]).await, [false]);
+
+    let (name, email): (String, String) = sqlx::query_as("SELECT name, email FROM customers WHERE id = ?")
+        .bind(customer)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((name.as_str(), email), ("Deleted customer", format!("deleted-{}@invalid", customer)));
+
+    let (street, city): (Option<String>, Option<String>) = sqlx::query_as("SELECT shipping_street, shipping_city FROM orders WHERE id = ?")
+        .bind(order)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((street, city.as_deref()), (None, Some("Bristol")));
+
+    // The email is free for a new customer again
+    let customer = json!({ "name": "Ada", "email": "ada@example.com", "address": "5 Mill Lane" });
+    let (status, _, body) = send(&app, Method::POST, "/api/v1/customers", &[], Some(customer)).await;
+    assert_eq!(status, StatusCode::CREATED, "unexpected body {}", body);
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn history_lists_the_writes_to_one_record_oldest_first(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let product = json!({ "name": "Rye bread", "description": null, "price": "3.50", "in_stock": true });
+    let (status, _, rye) = send(&app, Method::POST, "/api/v1/products", &[], Some(product.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let rye = rye["data"]["id"].as_i64().unwrap();
+    let (_, _, spelt) = send(&app, Method::POST, "/api/v1/products", &[], Some(product)).await;
+    let spelt = spelt["data"]["id"].as_i64().unwrap();
+
+    // Writes to the two products interleave; each history only holds its own, in order
+    for (id, patch) in [
+        (rye, json!({ "price": "3.80" })),
+        (spelt, json!({ "name": "Spelt bread" })),
+        (rye, json!({ "in_stock": false })),
+    ] {
+        let (status, _, _) = send(&app, Method::PATCH, &format!("/api/v1/products/{}", id), &[], Some(patch)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _, _) = send(&app, Method::DELETE, &format!("/api/v1/products/{}", rye), &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, body) = send(&app, Method::GET, &format!("/api/v1/products/{}/history", rye), &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = body["data"].as_array().unwrap();
+    let actions: Vec<&str> = entries.iter().map(|entry| entry["action"].as_str().unwrap()).collect();
+    assert_eq!(actions, ["create", "update", "update", "delete"]);
+
+    let changed = |entry: &Value| -> Vec<String> {
+        entry["changes"].as_array().unwrap().iter().map(|change| change["field"].as_str().unwrap().to_string()).collect()
+    };
+    assert!(changed(&entries[1]).contains(&"price".to_string()));
+    assert!(!changed(&entries[1]).contains(&"name".to_string()));
+    assert!(changed(&entries[2]).contains(&"in_stock".to_string()));
+    assert!(changed(&entries[3]).contains(&"deleted_at".to_string()));
+    assert!(entries.windows(2).all(|pair| pair[0]["id"].as_i64() < pair[1]["id"].as_i64()));
+}
+
+#[sqlx::test]
+#[ignore = "needs a MySQL server at DATABASE_URL"]
+async fn merge_moves_only_the_live_orders_of_the_duplicate(pool: MySqlPool) {
+    let app = app(pool.clone());
+    let kept = insert_customer(&pool, "Ada", "ada@example.com").await;
+    let duplicate = insert_customer(&pool, "Ada L.", "ada.l@example.com").await;
+    let product = insert_product(&pool, "Rye bread", None).await;
+    let live = insert_order(&pool, duplicate, product).await;
+    let trashed = insert_order(&pool, duplicate, product).await;
+    sqlx::query("UPDATE orders SET deleted_at = NOW() WHERE id = ?")
+        .bind(trashed)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, _, body) = send(&app, Method::POST, &format!("/api/v1/customers/{}/merge", kept), &[], Some(json!({ "duplicate_id": duplicate }))).await;
+    assert_eq!(status, StatusCode::OK, "unexpected body {}", body);
+
+    let owners: Vec<i32> = sqlx::query_scalar("SELECT customer_id FROM orders ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(owners, [kept, duplicate]);
+
+    // Only the moved order is audited as updated
+    let audited: Vec<i32> = sqlx::query_scalar("SELECT resource_id FROM audit_log WHERE resource = 'orders' AND action = 'update'")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(audited, [live]);
+}
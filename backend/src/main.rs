@@ -1,65 +1,175 @@
 use axum::http::{header::HeaderName, Method};
 use tower_http::cors::{CorsLayer, AllowOrigin};
-use sqlx::MySqlPool;
-use std::net::SocketAddr;
 use dotenv::dotenv;
 use axum::Router;
-use tracing_subscriber;
+use axum::middleware::from_fn;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use std::time::Duration;
+use crate::cache::ProductCache;
+use crate::config::Config;
+use crate::handlers::ApiState;
 use crate::utils::AppError;
-use tracing::error;
+use crate::middleware::idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER};
+use crate::middleware::actor::{actor, ACTOR_HEADER};
+use crate::middleware::request_id::{request_id, REQUEST_ID_HEADER};
+use crate::middleware::metrics::track_metrics;
+use crate::middleware::trace::trace_layer;
+use tracing::{error, info, warn};
 
+mod audit;
+mod cache;
+mod config;
+mod db;
+mod dto;
+mod metrics;
 mod utils;
+mod middleware;
 mod routes;
 mod handlers;
 mod models;
+mod openapi;
+mod shutdown;
+mod telemetry;
+mod trash;
 
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
-    // Initialize tracing for logging
-    tracing_subscriber::fmt::init();
-
     // Load environment variables from .env file
     dotenv().ok();
 
+    // Load and validate the configuration before anything else
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // Initialize tracing for logging and, if configured, OTLP span export
+    let tracer_provider = telemetry::init(&config.logging, &config.telemetry).unwrap_or_else(|e| {
+        eprintln!("Failed to set up trace export: {}", e);
+        std::process::exit(1);
+    });
+
     // Set up the MariaDB/MySQL connection pool
-    let database_url = std::env::var("DATABASE_URL").map_err(|_| AppError::InternalServerError)?;
-    let pool = MySqlPool::connect(&database_url)
+    let pool = db::connect_with_retry(&config.database, config.database_url())
         .await
         .map_err(AppError::DatabaseError)?;
 
     // Run database migrations
-    sqlx::migrate!()
+    db::MIGRATOR
         .run(&pool)
         .await
         .map_err(|e| {
-            error!("Failed to run migrations: {:?}", e);
+            error!(error = ?e, "Failed to run migrations");
             AppError::DatabaseError(e.into()) // Convert MigrateError to sqlx::Error
         })?;
     
+    // Cancelled on SIGINT/SIGTERM; background tasks are spawned on `tasks` and watch `shutdown`
+    let shutdown = CancellationToken::new();
+    let tasks = TaskTracker::new();
+    tokio::spawn(shutdown::listen_for_signals(shutdown.clone()));
+    tasks.spawn(middleware::idempotency::purge_expired(
+        pool.clone(),
+        Duration::from_secs(config.api.idempotency_window_secs),
+        shutdown.clone(),
+    ));
+    tasks.spawn(trash::purge_expired(
+        pool.clone(),
+        Duration::from_secs(u64::from(config.trash.retention_days) * 86_400),
+        shutdown.clone(),
+    ));
+
+    // Product reads are served through an optional in-process cache; deletes follow the configured policies
+    let api_state = ApiState {
+        pool: pool.clone(),
+        product_cache: ProductCache::new(&config.cache, config.features.product_cache),
+        deletion: config.deletion,
+    };
+
     // Create the Axum router with all routes
-    let app = Router::new()
-        .nest("/api", routes::create_routes(pool))
+    let mut app = Router::new()
+        .nest("/health", routes::create_health_routes(pool.clone()))
+        .nest("/api", routes::create_api_routes(api_state, &config.api, &config.features));
+
+    if config.features.metrics {
+        let handle = metrics::install_recorder().map_err(|e| {
+            error!(error = ?e, "Failed to install the metrics recorder");
+            AppError::InternalServerError
+        })?;
+        tasks.spawn(metrics::run_upkeep(handle.clone(), shutdown.clone()));
+        app = app
+            .nest("/metrics", routes::create_metrics_routes(pool.clone(), handle))
+            .layer(from_fn(track_metrics));
+    }
+
+    let app = app
         .layer(
             CorsLayer::new()
-                .allow_origin(AllowOrigin::exact(
-                    "http://localhost:3001".parse().map_err(|_| AppError::InternalServerError)?,
-                ))
+                .allow_origin(AllowOrigin::list(config.allowed_origins()))
                 .allow_methods(vec![
                     Method::GET,
                     Method::POST,
                     Method::PUT,
+                    Method::PATCH,
                     Method::DELETE,
                 ])
-                .allow_headers(vec![HeaderName::from_static("content-type")]),
-        );
+                .allow_headers(vec![
+                    HeaderName::from_static("content-type"),
+                    HeaderName::from_static("if-match"),
+                    HeaderName::from_static("if-none-match"),
+                    HeaderName::from_static("if-modified-since"),
+                    HeaderName::from_static(REQUEST_ID_HEADER),
+                    HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                    HeaderName::from_static(ACTOR_HEADER),
+                ])
+                .expose_headers(vec![
+                    HeaderName::from_static(REQUEST_ID_HEADER),
+                    HeaderName::from_static("etag"),
+                    HeaderName::from_static("last-modified"),
+                    HeaderName::from_static("cache-control"),
+                    HeaderName::from_static("location"),
+                    HeaderName::from_static("deprecation"),
+                    HeaderName::from_static("sunset"),
+                    HeaderName::from_static("link"),
+                    HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+                ]),
+        )
+        .layer(from_fn(actor))
+        .layer(trace_layer())
+        .layer(from_fn(request_id));
 
-    // Start the server
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    println!("Server running at http://{}", addr);
-    axum::Server::bind(&addr)
+    // Start the server; on shutdown it stops accepting connections and drains in-flight requests
+    let addr = config.server.bind_address;
+    info!(%addr, "Server running");
+    let server = axum::Server::bind(&addr)
         .serve(app.into_make_service())
-        .await
-        .map_err(|_| AppError::InternalServerError)?;
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned());
+
+    let drain_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let drain_deadline = async {
+        shutdown.cancelled().await;
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = server => result.map_err(|e| {
+            error!(error = ?e, "Server error");
+            AppError::InternalServerError
+        })?,
+        _ = drain_deadline => warn!(timeout = ?drain_timeout, "In-flight requests still running, forcing shutdown"),
+    }
+
+    // Stop background tasks, then release the database connections
+    tasks.close();
+    if tokio::time::timeout(drain_timeout, tasks.wait()).await.is_err() {
+        warn!(timeout = ?drain_timeout, "Background tasks still running, abandoning them");
+    }
+    pool.close().await;
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            warn!(error = ?e, "Failed to flush pending trace spans");
+        }
+    }
+    info!("Shutdown complete");
 
     Ok(())
 }
@@ -0,0 +1,74 @@
+use metrics::histogram;
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use pin_project_lite::pin_project;
+use std::{future::Future, pin::Pin, task::{Context, Poll}, time::{Duration, Instant}};
+use tokio_util::sync::CancellationToken;
+use tracing::{info_span, Span};
+use crate::middleware::metrics::current_route;
+
+// Latency buckets in seconds, shared by the HTTP and database histograms
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Install the global Prometheus recorder
+pub fn install_recorder() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_duration_seconds".to_string()), LATENCY_BUCKETS)?
+        .install_recorder()
+}
+
+/// Periodically drain the recorder's internal buffers until shutdown
+pub async fn run_upkeep(handle: PrometheusHandle, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(Duration::from_secs(5));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => handle.run_upkeep(),
+            _ = shutdown.cancelled() => break,
+        }
+    }
+}
+
+/// Record how long a database query takes, labelled with the query and the route of the
+/// request issuing it, and run it inside a `db.query` span so it shows up in exported traces
+pub trait TimedQuery: Future + Sized {
+    fn timed(self, query: &'static str) -> Timed<Self> {
+        let span = info_span!(
+            "db.query",
+            otel.name = format!("db {}", query),
+            otel.kind = "client",
+            db.system = "mysql",
+            query,
+        );
+        let route = current_route().unwrap_or_else(|| "background".to_string());
+        Timed { inner: self, query, route, span, started_at: None }
+    }
+}
+
+impl<F: Future> TimedQuery for F {}
+
+pin_project! {
+    pub struct Timed<F> {
+        #[pin]
+        inner: F,
+        query: &'static str,
+        route: String,
+        span: Span,
+        started_at: Option<Instant>,
+    }
+}
+
+impl<F: Future> Future for Timed<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _entered = this.span.enter();
+        let started_at = *this.started_at.get_or_insert_with(Instant::now);
+        let output = match this.inner.poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        let labels = [("query", this.query.to_string()), ("route", this.route.clone())];
+        histogram!("db_query_duration_seconds", &labels).record(started_at.elapsed().as_secs_f64());
+        Poll::Ready(output)
+    }
+}
@@ -0,0 +1,29 @@
+use axum::{http::Request, middleware::Next, response::Response};
+
+pub const ACTOR_HEADER: &str = "x-actor";
+// Recorded for requests that do not name their actor
+const ANONYMOUS: &str = "anonymous";
+
+tokio::task_local! {
+    static ACTOR: String;
+}
+
+/// Who the request currently being handled acts for; `None` outside a request (background jobs)
+pub fn current_actor() -> Option<String> {
+    ACTOR.try_with(|actor| actor.clone()).ok()
+}
+
+/// Take the acting user from `X-Actor` for the audit log; there is no authentication yet,
+/// so the value is whatever the client (e.g. the admin) reports
+pub async fn actor<B>(req: Request<B>, next: Next<B>) -> Response {
+    let actor = req
+        .headers()
+        .get(ACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && value.len() <= 255)
+        .unwrap_or(ANONYMOUS)
+        .to_string();
+
+    ACTOR.scope(actor, next.run(req)).await
+}
@@ -0,0 +1,31 @@
+use axum::{extract::State, http::{HeaderValue, Request}, middleware::Next, response::Response};
+use chrono::NaiveDate;
+
+// How a deprecated API version is announced to clients
+#[derive(Clone, Debug)]
+pub struct Deprecation {
+    // Base path clients should move to, sent as `Link: <...>; rel="successor-version"`
+    pub successor: &'static str,
+    // Date after which the version may be removed, sent as the `Sunset` header (RFC 8594)
+    pub sunset: Option<NaiveDate>,
+}
+
+/// Mark every response of a deprecated API version with `Deprecation`, `Link` and, if
+/// scheduled, `Sunset` headers
+pub async fn deprecation<B>(State(deprecation): State<Deprecation>, req: Request<B>, next: Next<B>) -> Response {
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", deprecation.successor)) {
+        headers.insert("link", link);
+    }
+    if let Some(sunset) = deprecation.sunset {
+        // HTTP-date at midnight UTC
+        let date = sunset.format("%a, %d %b %Y 00:00:00 GMT").to_string();
+        if let Ok(value) = HeaderValue::from_str(&date) {
+            headers.insert("sunset", value);
+        }
+    }
+    response
+}
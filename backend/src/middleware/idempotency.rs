@@ -0,0 +1,216 @@
+use axum::{
+    body::{boxed, Body, Full},
+    extract::{OriginalUri, State},
+    http::{HeaderName, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use http_body::{LengthLimitError, Limited};
+use metrics::counter;
+use sha2::{Digest, Sha256};
+use sqlx::{error::ErrorKind, FromRow, MySqlPool};
+use std::{borrow::Cow, time::Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use validator::{ValidationError, ValidationErrors};
+use crate::metrics::TimedQuery;
+use crate::utils::AppError;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+// Marks a response served from the idempotency store instead of the handler
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+// Where idempotent responses are kept and for how long a key is remembered
+#[derive(Clone)]
+pub struct Idempotency {
+    pub pool: MySqlPool,
+    pub window: Duration,
+    pub max_body_bytes: usize,
+}
+
+#[derive(FromRow)]
+struct StoredRequest {
+    request_hash: String,
+    response_status: Option<u16>,
+    response_headers: Option<String>,
+    response_body: Option<Vec<u8>>,
+}
+
+/// Make a `POST` sent with an `Idempotency-Key` safe to retry: the first request claims the key
+/// and its response is stored, retries within the window get that response replayed, and
+/// reusing the key for a different request is rejected with `422`
+pub async fn idempotency(State(store): State<Idempotency>, req: Request<Body>, next: Next<Body>) -> Result<Response, AppError> {
+    if req.method() != Method::POST || !req.headers().contains_key(IDEMPOTENCY_KEY_HEADER) {
+        return Ok(next.run(req).await);
+    }
+
+    let key = req
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 255)
+        .map(String::from)
+        .ok_or_else(|| invalid_request(IDEMPOTENCY_KEY_HEADER, "Idempotency-Key must be 1 to 255 visible ASCII characters"))?;
+
+    // Nested routers see a stripped path, so hash the one the client sent
+    let target = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.to_string())
+        .unwrap_or_else(|| req.uri().to_string());
+
+    let (parts, body) = req.into_parts();
+    // The body is buffered to be hashed, so cap it rather than reading whatever is sent
+    let body = hyper::body::to_bytes(Limited::new(body, store.max_body_bytes))
+        .await
+        .map_err(|e| match e.downcast::<LengthLimitError>() {
+            Ok(_) => AppError::PayloadTooLarge { limit: store.max_body_bytes },
+            Err(_) => invalid_request("body", "Could not read the request body"),
+        })?;
+    let hash = format!("{:x}", Sha256::new().chain_update(&target).chain_update(b"\n").chain_update(&body).finalize());
+
+    // A key whose window has passed may be claimed again
+    let window_secs = store.window.as_secs();
+    sqlx::query("DELETE FROM idempotency_keys WHERE idempotency_key = ? AND created_at <= NOW() - INTERVAL ? SECOND")
+        .bind(&key)
+        .bind(window_secs)
+        .execute(&store.pool)
+        .timed("idempotency_expire")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    let claimed = sqlx::query("INSERT INTO idempotency_keys (idempotency_key, request_hash) VALUES (?, ?)")
+        .bind(&key)
+        .bind(&hash)
+        .execute(&store.pool)
+        .timed("idempotency_claim")
+        .await;
+    match claimed {
+        Ok(_) => {}
+        Err(e) if e.as_database_error().is_some_and(|db| db.kind() == ErrorKind::UniqueViolation) => {
+            return replay(&store.pool, &key, &hash).await;
+        }
+        Err(e) => return Err(AppError::DatabaseError(e)),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // Server errors are not remembered, so the client can retry them with the same key
+    if response.status().is_server_error() {
+        release(&store.pool, &key).await;
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => {
+            warn!(error = %e, "Failed to buffer the response for its idempotency key");
+            release(&store.pool, &key).await;
+            return Err(AppError::InternalServerError);
+        }
+    };
+
+    let headers: Vec<(&str, &str)> = parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.as_str(), value)))
+        .collect();
+    let stored = sqlx::query("UPDATE idempotency_keys SET response_status = ?, response_headers = ?, response_body = ? WHERE idempotency_key = ?")
+        .bind(parts.status.as_u16())
+        .bind(serde_json::to_string(&headers).unwrap_or_else(|_| "[]".to_string()))
+        .bind(body.as_ref())
+        .bind(&key)
+        .execute(&store.pool)
+        .timed("idempotency_store")
+        .await;
+    if let Err(e) = stored {
+        // The response still goes out; a retry would run the request again
+        warn!(error = ?e, "Failed to store the response for its idempotency key");
+        release(&store.pool, &key).await;
+    }
+
+    Ok(Response::from_parts(parts, boxed(Full::from(body))))
+}
+
+/// Delete idempotency keys older than the window until shutdown
+pub async fn purge_expired(pool: MySqlPool, window: Duration, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(window.min(Duration::from_secs(3600)));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let purged = sqlx::query("DELETE FROM idempotency_keys WHERE created_at <= NOW() - INTERVAL ? SECOND")
+                    .bind(window.as_secs())
+                    .execute(&pool)
+                    .timed("idempotency_purge")
+                    .await;
+                match purged {
+                    Ok(result) if result.rows_affected() > 0 => info!(purged = result.rows_affected(), "Purged expired idempotency keys"),
+                    Ok(_) => {}
+                    Err(e) => warn!(error = ?e, "Failed to purge expired idempotency keys"),
+                }
+            }
+            _ = shutdown.cancelled() => break,
+        }
+    }
+}
+
+// Answer a retry from the stored response of the request that claimed the key
+async fn replay(pool: &MySqlPool, key: &str, hash: &str) -> Result<Response, AppError> {
+    let stored = sqlx::query_as::<_, StoredRequest>(
+        "SELECT request_hash, response_status, response_headers, response_body FROM idempotency_keys WHERE idempotency_key = ?",
+    )
+    .bind(key)
+    .fetch_optional(pool)
+    .timed("idempotency_replay")
+    .await
+    .map_err(AppError::DatabaseError)?;
+
+    let in_progress = || AppError::Conflict {
+        message: "A request with this Idempotency-Key is still being processed; retry later".to_string(),
+        fields: None,
+    };
+    // Gone again: the first request failed and released the key in the meantime
+    let Some(stored) = stored else {
+        return Err(in_progress());
+    };
+    if stored.request_hash != hash {
+        return Err(AppError::IdempotencyKeyReused { key: key.to_string() });
+    }
+    let Some(status) = stored.response_status.and_then(|status| StatusCode::from_u16(status).ok()) else {
+        return Err(in_progress());
+    };
+
+    counter!("idempotent_replays_total").increment(1);
+    let mut response = Response::new(boxed(Full::from(stored.response_body.unwrap_or_default())));
+    *response.status_mut() = status;
+    let headers: Vec<(String, String)> = stored
+        .response_headers
+        .and_then(|headers| serde_json::from_str(&headers).ok())
+        .unwrap_or_default();
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response.headers_mut().insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    Ok(response)
+}
+
+// Forget a claimed key so the request can be retried with it
+async fn release(pool: &MySqlPool, key: &str) {
+    let released = sqlx::query("DELETE FROM idempotency_keys WHERE idempotency_key = ? AND response_status IS NULL")
+        .bind(key)
+        .execute(pool)
+        .timed("idempotency_release")
+        .await;
+    if let Err(e) = released {
+        warn!(error = ?e, "Failed to release an idempotency key");
+    }
+}
+
+fn invalid_request(field: &'static str, message: &'static str) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new("invalid").with_message(Cow::from(message)));
+    AppError::ValidationError(errors)
+}
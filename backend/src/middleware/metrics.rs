@@ -0,0 +1,34 @@
+use axum::{extract::MatchedPath, http::Request, middleware::Next, response::Response};
+use metrics::{counter, histogram};
+use std::time::Instant;
+
+tokio::task_local! {
+    static ROUTE: String;
+}
+
+/// Route template of the request currently being handled; `None` outside a request (background jobs)
+pub fn current_route() -> Option<String> {
+    ROUTE.try_with(|route| route.clone()).ok()
+}
+
+/// Count requests and record their latency per route template, method and status
+pub async fn track_metrics<B>(req: Request<B>, next: Next<B>) -> Response {
+    let started_at = Instant::now();
+    // Route templates (e.g. `/api/products/:id`) keep label cardinality bounded
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+
+    // Database queries issued while handling the request are labelled with its route
+    let response = ROUTE.scope(path.clone(), next.run(req)).await;
+
+    let status = response.status().as_u16().to_string();
+    let labels = [("method", method), ("path", path), ("status", status)];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(started_at.elapsed().as_secs_f64());
+
+    response
+}
@@ -0,0 +1,6 @@
+pub mod request_id;
+pub mod metrics;
+pub mod trace;
+pub mod deprecation;
+pub mod idempotency;
+pub mod actor;
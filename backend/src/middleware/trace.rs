@@ -0,0 +1,56 @@
+use axum::{extract::MatchedPath, http::{Request, Response}};
+use std::time::Duration;
+use tower_http::{
+    classify::{ServerErrorsAsFailures, SharedClassifier},
+    trace::{DefaultOnRequest, MakeSpan, OnResponse, TraceLayer},
+};
+use tracing::{field::Empty, info, info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use crate::middleware::request_id::RequestId;
+use crate::telemetry::extract_context;
+
+/// One span per request carrying method, path, route, request id, status and latency
+pub fn trace_layer() -> TraceLayer<SharedClassifier<ServerErrorsAsFailures>, RequestSpan, DefaultOnRequest, RecordResponse> {
+    TraceLayer::new_for_http()
+        .make_span_with(RequestSpan)
+        .on_response(RecordResponse)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RequestSpan;
+
+impl<B> MakeSpan<B> for RequestSpan {
+    fn make_span(&mut self, req: &Request<B>) -> Span {
+        let request_id = req.extensions().get::<RequestId>().map(|id| id.0.as_str()).unwrap_or("-");
+        let route = req.extensions().get::<MatchedPath>().map(|path| path.as_str()).unwrap_or("-");
+
+        let span = info_span!(
+            "request",
+            otel.name = format!("{} {}", req.method(), route),
+            otel.kind = "server",
+            request_id,
+            method = %req.method(),
+            path = req.uri().path(),
+            route,
+            status = Empty,
+            latency_ms = Empty,
+        );
+
+        // Continue the caller's trace when a `traceparent` header is present
+        span.set_parent(extract_context(req.headers()));
+        span
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RecordResponse;
+
+impl<B> OnResponse<B> for RecordResponse {
+    fn on_response(self, res: &Response<B>, latency: Duration, span: &Span) {
+        let status = res.status().as_u16();
+        let latency_ms = latency.as_millis() as u64;
+        span.record("status", status);
+        span.record("latency_ms", latency_ms);
+        info!(status, latency_ms, "Request completed");
+    }
+}
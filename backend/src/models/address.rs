@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+use utoipa::ToSchema;
+use crate::utils::{validate_address_type, validate_country_code};
+
+#[derive(Debug, FromRow)]
+pub struct CustomerAddress {
+    pub id: i32,
+    pub customer_id: i32,
+    pub address_type: String,
+    pub street: String,
+    pub city: String,
+    pub postal_code: String,
+    pub country_code: String,
+    pub is_default: bool,
+    pub version: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct CreateAddress {
+    #[validate(custom(function = "validate_address_type"))]
+    #[schema(pattern = "^(billing|shipping)$", example = "shipping")]
+    pub address_type: String,
+
+    #[validate(length(min = 1, message = "Street is required"))]
+    #[schema(min_length = 1)]
+    pub street: String,
+
+    #[validate(length(min = 1, message = "City is required"))]
+    #[schema(min_length = 1)]
+    pub city: String,
+
+    #[validate(length(min = 1, max = 20, message = "Postal code must be between 1 and 20 characters"))]
+    #[schema(min_length = 1, max_length = 20)]
+    pub postal_code: String,
+
+    /// ISO 3166-1 alpha-2 code
+    #[validate(custom(function = "validate_country_code"))]
+    #[schema(pattern = "^[A-Z]{2}$", example = "SK")]
+    pub country_code: String,
+
+    #[serde(default)]
+    pub is_default: bool,
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{types::Json, FromRow};
+use utoipa::IntoParams;
+
+#[derive(Debug, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub resource: String,
+    pub resource_id: i32,
+    pub before_data: Option<Json<Value>>,
+    pub after_data: Option<Json<Value>>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Query string of the audit log listing; every filter is optional
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct AuditLogParams {
+    /// Table of the records, e.g. `orders` or `customer_addresses`
+    pub resource: Option<String>,
+    /// Id of a single record (use with `resource`)
+    pub resource_id: Option<i32>,
+    /// Value of `X-Actor` the writes were made with
+    pub actor: Option<String>,
+    /// Only entries written at or after this time (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries written before this time (RFC 3339)
+    pub to: Option<DateTime<Utc>>,
+    /// Entries per page, newest first (default 100, at most 1000)
+    pub limit: Option<u32>,
+    /// Entries to skip
+    pub offset: Option<u32>,
+}
@@ -1,20 +1,83 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
+use utoipa::ToSchema;
+use crate::utils::{is_cleared, patch_field};
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Debug, FromRow)]
 pub struct Customer {
     pub id: i32,
     pub name: String,
     pub email: String,
     pub address: String,
+    pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Validate)]
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct CreateCustomer {
     #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(min_length = 1)]
     pub name: String,
+    /// Unique, compared case-insensitively
     #[validate(email(message = "Email must be valid"))]
+    #[schema(format = "email")]
     pub email: String,
     pub address: String,
 }
+
+// One item of a bulk update: the customer to overwrite and its new values, validated like `CreateCustomer`
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BulkUpdateCustomer {
+    #[schema(minimum = 1)]
+    pub id: i32,
+    /// Version the values are based on; a customer that has moved on fails the batch with 412
+    pub version: Option<i32>,
+    #[serde(flatten)]
+    pub customer: CreateCustomer,
+}
+
+impl Validate for BulkUpdateCustomer {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.customer.validate()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
+pub struct MergeCustomer {
+    #[validate(range(min = 1, message = "Duplicate customer ID must be a positive number"))]
+    #[schema(minimum = 1)]
+    pub duplicate_id: i32,
+}
+
+// JSON Merge Patch body: omitted fields are left untouched
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct PatchCustomer {
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(value_type = Option<String>, min_length = 1)]
+    pub name: Option<Option<String>>,
+    /// Unique, compared case-insensitively
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(email(message = "Email must be valid"))]
+    #[schema(value_type = Option<String>, format = "email")]
+    pub email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "patch_field")]
+    #[schema(value_type = Option<String>)]
+    pub address: Option<Option<String>>,
+}
+
+impl PatchCustomer {
+    // NOT NULL columns the patch tries to set to `null`
+    pub fn cleared_required(&self) -> Vec<&'static str> {
+        [
+            ("name", is_cleared(&self.name)),
+            ("email", is_cleared(&self.email)),
+            ("address", is_cleared(&self.address)),
+        ]
+        .into_iter()
+        .filter_map(|(field, cleared)| cleared.then_some(field))
+        .collect()
+    }
+}
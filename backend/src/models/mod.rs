@@ -1,3 +1,54 @@
+pub mod address;
+pub mod audit;
 pub mod customer;
 pub mod order;
 pub mod product;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+// Query string of the bulk-delete endpoints
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct BulkDeleteParams {
+    /// Roll the whole batch back if any id is missing or still referenced
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+// Query string of the list and get endpoints: soft-deleted rows are hidden unless asked for
+#[derive(Deserialize, Debug, Default, IntoParams)]
+pub struct TrashParams {
+    /// Also return soft-deleted rows
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// Return only soft-deleted rows (the trash)
+    #[serde(default)]
+    pub only_deleted: bool,
+}
+
+impl TrashParams {
+    // SQL condition selecting the requested rows
+    pub fn condition(&self) -> &'static str {
+        if self.only_deleted {
+            "deleted_at IS NOT NULL"
+        } else if self.include_deleted {
+            "TRUE"
+        } else {
+            "deleted_at IS NULL"
+        }
+    }
+
+    // Whether only live rows were asked for, the default
+    pub fn live_only(&self) -> bool {
+        !self.only_deleted && !self.include_deleted
+    }
+
+    // Whether a row with this `deleted_at` is among the requested ones
+    pub fn shows(&self, deleted_at: Option<DateTime<Utc>>) -> bool {
+        match deleted_at {
+            Some(_) => self.only_deleted || self.include_deleted,
+            None => !self.only_deleted,
+        }
+    }
+}
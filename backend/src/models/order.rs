@@ -1,30 +1,124 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use validator::Validate;
-use chrono::NaiveDate;
-use crate::utils::validate_date;
+use validator::{Validate, ValidationErrors};
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::utils::{is_cleared, patch_field, validate_date};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Debug, FromRow)]
 pub struct Order {
     pub id: i32,
     pub customer_id: i32,
     pub product_id: i32,
     pub quantity: i32,
     pub order_date: NaiveDate, 
+    pub shipping_street: Option<String>,
+    pub shipping_city: Option<String>,
+    pub shipping_postal_code: Option<String>,
+    pub shipping_country_code: Option<String>,
+    pub billing_street: Option<String>,
+    pub billing_city: Option<String>,
+    pub billing_postal_code: Option<String>,
+    pub billing_country_code: Option<String>,
+    pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Validate)]
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct CreateOrder {
     #[validate(range(min = 1, message = "Customer ID must be a positive number"))]
+    #[schema(minimum = 1)]
     pub customer_id: i32,
 
     #[validate(range(min = 1, message = "Product ID must be a positive number"))]
+    #[schema(minimum = 1)]
     pub product_id: i32,
 
     #[validate(range(min = 1, message = "Quantity must be a positive number"))]
+    #[schema(minimum = 1)]
     pub quantity: i32,
 
+    /// Must be on or after 2020-01-01
     #[validate(custom(function = "validate_date"))]
     pub order_date: NaiveDate,
+
+    /// When omitted, a new order (or one moved to another customer) gets the customer's default shipping address
+    #[validate(range(min = 1, message = "Shipping address ID must be a positive number"))]
+    #[schema(minimum = 1)]
+    pub shipping_address_id: Option<i32>,
+
+    /// When omitted, a new order (or one moved to another customer) gets the customer's default billing address
+    #[validate(range(min = 1, message = "Billing address ID must be a positive number"))]
+    #[schema(minimum = 1)]
+    pub billing_address_id: Option<i32>,
+}
+
+// One item of a bulk update: the order to overwrite and its new values, validated like `CreateOrder`
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BulkUpdateOrder {
+    #[schema(minimum = 1)]
+    pub id: i32,
+    /// Version of the order the values are based on; if the order has been changed since, the whole batch fails with 412
+    pub version: Option<i32>,
+    #[serde(flatten)]
+    pub order: CreateOrder,
+}
+
+impl Validate for BulkUpdateOrder {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.order.validate()
+    }
+}
+
+
+// JSON Merge Patch body: omitted fields are left untouched
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct PatchOrder {
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(range(min = 1, message = "Customer ID must be a positive number"))]
+    #[schema(value_type = Option<i32>, minimum = 1)]
+    pub customer_id: Option<Option<i32>>,
+
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(range(min = 1, message = "Product ID must be a positive number"))]
+    #[schema(value_type = Option<i32>, minimum = 1)]
+    pub product_id: Option<Option<i32>>,
+
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(range(min = 1, message = "Quantity must be a positive number"))]
+    #[schema(value_type = Option<i32>, minimum = 1)]
+    pub quantity: Option<Option<i32>>,
+
+    /// Must be on or after 2020-01-01
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(custom(function = "validate_date"))]
+    #[schema(value_type = Option<NaiveDate>)]
+    pub order_date: Option<Option<NaiveDate>>,
+
+    /// Copies the given address onto the order; `null` removes the shipping snapshot
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(range(min = 1, message = "Shipping address ID must be a positive number"))]
+    #[schema(value_type = Option<i32>, minimum = 1)]
+    pub shipping_address_id: Option<Option<i32>>,
+
+    /// Copies the given address onto the order; `null` removes the billing snapshot
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(range(min = 1, message = "Billing address ID must be a positive number"))]
+    #[schema(value_type = Option<i32>, minimum = 1)]
+    pub billing_address_id: Option<Option<i32>>,
 }
 
+impl PatchOrder {
+    // NOT NULL columns the patch tries to set to `null`
+    pub fn cleared_required(&self) -> Vec<&'static str> {
+        [
+            ("customer_id", is_cleared(&self.customer_id)),
+            ("product_id", is_cleared(&self.product_id)),
+            ("quantity", is_cleared(&self.quantity)),
+            ("order_date", is_cleared(&self.order_date)),
+        ]
+        .into_iter()
+        .filter_map(|(field, cleared)| cleared.then_some(field))
+        .collect()
+    }
+}
@@ -1,24 +1,83 @@
-use crate::utils::validate_decimal_range;
+use crate::utils::{is_cleared, patch_field, validate_decimal_range};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 use sqlx::FromRow;
 use rust_decimal::Decimal;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Debug, Clone, FromRow)]
 pub struct Product {
     pub id: i32,
     pub name: String,
     pub description: Option<String>,
     pub price: Decimal,
     pub in_stock: bool,
+    pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Validate)]
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct CreateProduct {
     #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(min_length = 1)]
     pub name: String,
     pub description: Option<String>,
+    /// Must not be negative
     #[validate(custom(function = "validate_decimal_range"))]
+    #[schema(example = "4.99")]
     pub price: Decimal,
     pub in_stock: bool,
 }
+
+// One item of a bulk update: the product to overwrite and its new values, validated like `CreateProduct`
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BulkUpdateProduct {
+    #[schema(minimum = 1)]
+    pub id: i32,
+    /// Version the values are based on; a product that has moved on fails the batch with 412
+    pub version: Option<i32>,
+    #[serde(flatten)]
+    pub product: CreateProduct,
+}
+
+impl Validate for BulkUpdateProduct {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.product.validate()
+    }
+}
+
+// JSON Merge Patch body: omitted fields are left untouched, `null` clears nullable columns
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct PatchProduct {
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(length(min = 1, message = "Name is required"))]
+    #[schema(value_type = Option<String>, min_length = 1)]
+    pub name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "patch_field")]
+    #[schema(value_type = Option<String>)]
+    pub description: Option<Option<String>>,
+    /// Must not be negative
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(custom(function = "validate_decimal_range"))]
+    #[schema(value_type = Option<String>, example = "4.99")]
+    pub price: Option<Option<Decimal>>,
+    #[serde(default, deserialize_with = "patch_field")]
+    #[schema(value_type = Option<bool>)]
+    pub in_stock: Option<Option<bool>>,
+}
+
+impl PatchProduct {
+    // NOT NULL columns the patch tries to set to `null`
+    pub fn cleared_required(&self) -> Vec<&'static str> {
+        [
+            ("name", is_cleared(&self.name)),
+            ("price", is_cleared(&self.price)),
+            ("in_stock", is_cleared(&self.in_stock)),
+        ]
+        .into_iter()
+        .filter_map(|(field, cleared)| cleared.then_some(field))
+        .collect()
+    }
+}
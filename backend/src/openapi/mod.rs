@@ -0,0 +1,169 @@
+use axum::{Json, response::Html};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::{OpenApi, ToSchema};
+use crate::handlers::{addresses, audit, customers, orders, products};
+use crate::dto::{
+    v1::{AddressDto, AuditEntryDto, BlockedId, BulkDeleteReport, CustomerDto, FieldChange, HistoryEntryDto, OrderDto, ProductDto},
+    v2::{OrderAddressDto, OrderV2Dto},
+};
+use crate::models::{
+    address::CreateAddress,
+    customer::{BulkUpdateCustomer, CreateCustomer, MergeCustomer, PatchCustomer},
+    order::{BulkUpdateOrder, CreateOrder, PatchOrder},
+    product::{BulkUpdateProduct, CreateProduct, PatchProduct},
+};
+
+// Every successful response wraps its payload in `data`
+#[derive(Serialize, ToSchema)]
+#[aliases(
+    ProductResponse = DataResponse<ProductDto>,
+    ProductListResponse = DataResponse<Vec<ProductDto>>,
+    CustomerResponse = DataResponse<CustomerDto>,
+    CustomerListResponse = DataResponse<Vec<CustomerDto>>,
+    OrderResponse = DataResponse<OrderDto>,
+    OrderListResponse = DataResponse<Vec<OrderDto>>,
+    OrderV2Response = DataResponse<OrderV2Dto>,
+    OrderV2ListResponse = DataResponse<Vec<OrderV2Dto>>,
+    AddressResponse = DataResponse<AddressDto>,
+    AddressListResponse = DataResponse<Vec<AddressDto>>,
+    MessageResponse = DataResponse<String>,
+    BulkDeleteResponse = DataResponse<BulkDeleteReport>,
+    AuditEntryResponse = DataResponse<AuditEntryDto>,
+    AuditLogResponse = DataResponse<Vec<AuditEntryDto>>,
+    HistoryResponse = DataResponse<Vec<HistoryEntryDto>>,
+)]
+pub struct DataResponse<T> {
+    pub data: T,
+}
+
+/// RFC 7807 error body (`application/problem+json`)
+#[derive(Serialize, ToSchema)]
+pub struct ProblemDetails {
+    #[schema(example = "/problems/not-found")]
+    pub r#type: String,
+    #[schema(example = "Not Found")]
+    pub title: String,
+    #[schema(example = 404)]
+    pub status: u16,
+    #[schema(example = "No product found with id 42")]
+    pub detail: String,
+    /// Stable machine-readable error code
+    #[schema(example = "not_found")]
+    pub code: String,
+    pub request_id: Option<String>,
+    /// Per-field errors: `{ field: [{ code, message }] }`
+    #[schema(value_type = Option<Object>)]
+    pub fields: Option<Value>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Bio Craft Foods API", description = "Products, customers and orders of the Bio Craft Foods shop. `/api/v2` shares every v1 route except those returning orders; the unversioned `/api` is a deprecated alias of `/api/v1`."),
+    paths(
+        products::list_products,
+        products::get_product,
+        products::create_product,
+        products::update_product,
+        products::patch_product,
+        products::delete_product,
+        products::restore_product,
+        products::bulk_create_products,
+        products::bulk_update_products,
+        products::delete_products,
+        customers::list_customers,
+        customers::get_customer,
+        customers::create_customer,
+        customers::update_customer,
+        customers::patch_customer,
+        customers::delete_customer,
+        customers::restore_customer,
+        customers::merge_customers,
+        customers::bulk_create_customers,
+        customers::bulk_update_customers,
+        customers::delete_customers,
+        addresses::list_addresses,
+        addresses::get_address,
+        addresses::create_address,
+        addresses::update_address,
+        addresses::delete_address,
+        orders::list_orders,
+        orders::get_order,
+        orders::list_orders_v2,
+        orders::get_order_v2,
+        orders::create_order_v2,
+        orders::update_order_v2,
+        orders::patch_order_v2,
+        orders::create_order,
+        orders::update_order,
+        orders::patch_order,
+        orders::delete_order,
+        orders::restore_order,
+        orders::restore_order_v2,
+        orders::delete_orders,
+        orders::bulk_create_orders,
+        orders::bulk_update_orders,
+        orders::bulk_create_orders_v2,
+        orders::bulk_update_orders_v2,
+        audit::list_audit_log,
+        audit::get_audit_entry,
+        audit::product_history,
+        audit::customer_history,
+        audit::order_history,
+    ),
+    components(schemas(
+        ProductDto, CreateProduct, PatchProduct, BulkUpdateProduct,
+        CustomerDto, CreateCustomer, MergeCustomer, PatchCustomer, BulkUpdateCustomer,
+        AddressDto, CreateAddress,
+        OrderDto, OrderV2Dto, OrderAddressDto, CreateOrder, PatchOrder, BulkUpdateOrder,
+        ProductResponse, ProductListResponse,
+        CustomerResponse, CustomerListResponse,
+        OrderResponse, OrderListResponse,
+        OrderV2Response, OrderV2ListResponse,
+        AddressResponse, AddressListResponse,
+        MessageResponse,
+        BulkDeleteReport, BlockedId, BulkDeleteResponse,
+        AuditEntryDto, AuditEntryResponse, AuditLogResponse,
+        HistoryEntryDto, FieldChange, HistoryResponse,
+        ProblemDetails,
+    )),
+    tags(
+        (name = "products", description = "Product catalogue"),
+        (name = "customers", description = "Customers and their addresses"),
+        (name = "orders", description = "Customer orders"),
+        (name = "audit", description = "Who changed what, and when"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI 3 document describing the API
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI rendering `/api/openapi.json`
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+// The assets are pinned to one release so their integrity hashes keep matching; bump both together
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8" />
+    <title>Bio Craft Foods API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui.css"
+        integrity="sha384-wxLW6kwyHktdDGr6Pv1zgm/VGJh99lfUbzSn6HNHBENZlCN7W602k9VkGdxuFvPn" crossorigin="anonymous" />
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui-bundle.js"
+        integrity="sha384-wmyclcVGX/WhUkdkATwhaK1X1JtiNrr2EoYJ+diV3vj4v6OC5yCeSu+yW13SYJep" crossorigin="anonymous"></script>
+    <script>
+        window.onload = () => {
+            window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+        };
+    </script>
+</body>
+</html>
+"##;
@@ -1,24 +1,123 @@
 use sqlx::MySqlPool;
 #[allow(unused_imports)]
-use axum::{Router, routing::{get, post, put, delete}};
-use crate::handlers::{customers, orders, products};
+use axum::{Router, routing::{get, post, put, patch, delete}};
+use axum::middleware::from_fn_with_state;
+use crate::config::{ApiConfig, FeaturesConfig};
+use crate::middleware::deprecation::{deprecation, Deprecation};
+use crate::middleware::idempotency::{idempotency, Idempotency};
+use std::time::Duration;
+use crate::handlers::{addresses, audit, customers, health, metrics, orders, products, ApiState};
+use crate::openapi;
+use metrics_exporter_prometheus::PrometheusHandle;
 
-pub fn create_routes(pool: MySqlPool) -> Router {
-    Router::new()
+// Versions are mounted side by side under `/api/v1` and `/api/v2`; the unversioned `/api`
+// keeps serving v1 for existing clients but is announced as deprecated. Every `POST` honours
+// an `Idempotency-Key`
+pub fn create_api_routes(state: ApiState, api: &ApiConfig, features: &FeaturesConfig) -> Router {
+    let mut v1 = create_v1_routes(state.clone(), features);
+    if let Some(sunset) = api.v1_sunset {
+        v1 = v1.layer(from_fn_with_state(Deprecation { successor: "/api/v2", sunset: Some(sunset) }, deprecation));
+    }
+
+    let unversioned = create_v1_routes(state.clone(), features)
+        .layer(from_fn_with_state(Deprecation { successor: "/api/v1", sunset: api.unversioned_sunset }, deprecation));
+
+    let store = Idempotency {
+        pool: state.pool.clone(),
+        window: Duration::from_secs(api.idempotency_window_secs),
+        max_body_bytes: api.idempotency_max_body_bytes,
+    };
+    let mut router = Router::new()
+        .nest("/v1", v1)
+        .nest("/v2", create_v2_routes(state, features))
+        .merge(unversioned)
+        .layer(from_fn_with_state(store, idempotency));
+
+    if features.api_docs {
+        router = router
+            .route("/openapi.json", get(openapi::openapi_json))
+            .route("/docs", get(openapi::swagger_ui));
+    }
+
+    router
+}
+
+// v1: the original API shape
+fn create_v1_routes(state: ApiState, features: &FeaturesConfig) -> Router {
+    create_resource_routes(features)
+        .route("/orders", get(orders::list_orders).post(orders::create_order))
+        .route("/orders/bulk-create", post(orders::bulk_create_orders))
+        .route("/orders/bulk-update", post(orders::bulk_update_orders))
+        .route("/orders/:id", get(orders::get_order).put(orders::update_order).patch(orders::patch_order))
+        .route("/orders/:id/restore", post(orders::restore_order))
+        .with_state(state)
+}
+
+// v2: orders embed their address snapshots as objects
+fn create_v2_routes(state: ApiState, features: &FeaturesConfig) -> Router {
+    create_resource_routes(features)
+        .route("/orders", get(orders::list_orders_v2).post(orders::create_order_v2))
+        .route("/orders/bulk-create", post(orders::bulk_create_orders_v2))
+        .route("/orders/bulk-update", post(orders::bulk_update_orders_v2))
+        .route("/orders/:id", get(orders::get_order_v2).put(orders::update_order_v2).patch(orders::patch_order_v2))
+        .route("/orders/:id/restore", post(orders::restore_order_v2))
+        .with_state(state)
+}
+
+// Routes whose shape is shared by every API version
+fn create_resource_routes(features: &FeaturesConfig) -> Router<ApiState> {
+    let mut router = Router::new()
         // Products routes
         .route("/products", get(products::list_products).post(products::create_product))
+        .route("/products/bulk-create", post(products::bulk_create_products))
+        .route("/products/bulk-update", post(products::bulk_update_products))
         .route("/products/bulk-delete", post(products::delete_products)) 
-        .route("/products/:id", get(products::get_product).put(products::update_product).delete(products::delete_product))
+        .route("/products/:id", get(products::get_product).put(products::update_product).patch(products::patch_product).delete(products::delete_product))
+        .route("/products/:id/restore", post(products::restore_product))
+        .route("/products/:id/history", get(audit::product_history))
         
         // Customers routes
         .route("/customers", get(customers::list_customers).post(customers::create_customer))
+        .route("/customers/bulk-create", post(customers::bulk_create_customers))
+        .route("/customers/bulk-update", post(customers::bulk_update_customers))
         .route("/customers/bulk-delete", post(customers::delete_customers)) 
-        .route("/customers/:id", get(customers::get_customer).put(customers::update_customer).delete(customers::delete_customer))
+        .route("/customers/:id", get(customers::get_customer).put(customers::update_customer).patch(customers::patch_customer).delete(customers::delete_customer))
+        .route("/customers/:id/restore", post(customers::restore_customer))
+        .route("/customers/:id/history", get(audit::customer_history))
+
+        // Customer addresses routes
+        .route("/customers/:id/addresses", get(addresses::list_addresses).post(addresses::create_address))
+        .route("/customers/:id/addresses/:address_id", get(addresses::get_address).put(addresses::update_address).delete(addresses::delete_address))
         
-        // Orders routes
-        .route("/orders", get(orders::list_orders).post(orders::create_order))
+        // Orders routes; everything returning an order is added per version
         .route("/orders/bulk-delete", post(orders::delete_orders)) 
-        .route("/orders/:id", get(orders::get_order).put(orders::update_order).delete(orders::delete_order))
-        
-        .with_state(pool)
+        .route("/orders/:id", delete(orders::delete_order))
+        .route("/orders/:id/history", get(audit::order_history))
+
+        // Audit log, read-only
+        .route("/audit-log", get(audit::list_audit_log))
+        .route("/audit-log/:id", get(audit::get_audit_entry));
+
+    // Optional routes behind feature toggles
+    if features.customer_merge {
+        router = router.route("/customers/:id/merge", post(customers::merge_customers));
+    }
+
+    router
+}
+
+// Health checks for load balancers, mounted outside `/api`
+pub fn create_health_routes(pool: MySqlPool) -> Router {
+    Router::new()
+        .route("/", get(health::health))
+        .route("/live", get(health::live))
+        .route("/ready", get(health::ready))
+        .with_state(health::HealthState::new(pool))
+}
+
+// Prometheus scrape endpoint, mounted outside `/api`
+pub fn create_metrics_routes(pool: MySqlPool, handle: PrometheusHandle) -> Router {
+    Router::new()
+        .route("/", get(metrics::metrics))
+        .with_state(metrics::MetricsState { pool, handle })
 }
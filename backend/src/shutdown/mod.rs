@@ -0,0 +1,31 @@
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Cancel `shutdown` on the first SIGINT (Ctrl+C) or SIGTERM
+pub async fn listen_for_signals(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+        _ = shutdown.cancelled() => return,
+    }
+
+    shutdown.cancel();
+}
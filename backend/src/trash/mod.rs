@@ -0,0 +1,121 @@
+use sqlx::{Acquire, MySqlPool};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use crate::audit::{self, AuditAction};
+use crate::metrics::TimedQuery;
+use crate::utils::{blocking_table, owned_ids, unreferenced, AppError};
+
+// Children before parents, so a purged order no longer holds its customer or product back
+const PURGE_ORDER: [&str; 3] = ["orders", "customers", "products"];
+
+/// Permanently delete rows that have been in the trash longer than the retention period,
+/// checking hourly until shutdown
+pub async fn purge_expired(pool: MySqlPool, retention: Duration, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(Duration::from_secs(3600));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                for table in PURGE_ORDER {
+                    match purge_table(&pool, table, retention).await {
+                        Ok(0) => {}
+                        Ok(purged) => info!(resource = table, purged, "Purged expired rows from the trash"),
+                        Err(e) => warn!(resource = table, error = ?e, "Failed to purge the trash"),
+                    }
+                }
+            }
+            _ = shutdown.cancelled() => break,
+        }
+    }
+}
+
+// Hard-delete one table's expired rows. Rows live orders still reference (an anonymised
+// customer) are kept until those orders go; a row still referenced by a younger trashed row
+// is skipped until that one is purged too
+async fn purge_table(pool: &MySqlPool, table: &'static str, retention: Duration) -> Result<u64, AppError> {
+    let expired = format!("SELECT id FROM {} WHERE deleted_at <= NOW() - INTERVAL ? SECOND{}", table, unreferenced(table));
+    let ids: Vec<i32> = sqlx::query_scalar(&expired)
+        .bind(retention.as_secs())
+        .fetch_all(pool)
+        .timed("purge_trash")
+        .await?;
+
+    let mut purged = 0;
+    let mut tx = pool.begin().await?;
+    let delete = format!("DELETE FROM {} WHERE id = ? AND deleted_at IS NOT NULL", table);
+    for id in ids {
+        let mut savepoint = tx.begin().await?;
+        let before = audit::snapshot(&mut savepoint, table, id).await?;
+        // A customer's saved addresses go with it through `ON DELETE CASCADE`
+        let addresses = match table {
+            "customers" => {
+                let address_ids = owned_ids(&mut savepoint, "customer_addresses", id).await?;
+                audit::snapshots(&mut savepoint, "customer_addresses", &address_ids).await?
+            }
+            _ => Vec::new(),
+        };
+        match sqlx::query(&delete).bind(id).execute(&mut *savepoint).timed("purge_trash").await {
+            // Restored since it was listed, so nothing was deleted
+            Ok(done) if done.rows_affected() == 0 => savepoint.rollback().await?,
+            Ok(done) => {
+                audit::record(&mut savepoint, AuditAction::Purge, table, id, before).await?;
+                audit::record_all(&mut savepoint, AuditAction::Purge, "customer_addresses", addresses).await?;
+                savepoint.commit().await?;
+                purged += done.rows_affected();
+            }
+            Err(e) => {
+                let Some(dependent) = blocking_table(&e) else {
+                    return Err(AppError::DatabaseError(e));
+                };
+                warn!(resource = table, id, dependent = %dependent, "Skipped purging a row still referenced from the trash");
+                savepoint.rollback().await?;
+            }
+        }
+    }
+    tx.commit().await?;
+    Ok(purged)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    async fn insert(pool: &MySqlPool, statement: &str) -> i32 {
+        sqlx::query(statement).execute(pool).await.expect("fixture").last_insert_id() as i32
+    }
+
+    async fn exists(pool: &MySqlPool, table: &str, id: i32) -> bool {
+        sqlx::query_scalar(&format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?)", table))
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn purge_keeps_rows_referenced_by_live_or_younger_trashed_orders(pool: MySqlPool) {
+        // An anonymised customer trashed long ago whose order is still live
+        let customer = insert(&pool, "INSERT INTO customers (name, email, address, deleted_at) VALUES ('Deleted customer', 'deleted-1@invalid', '', NOW() - INTERVAL 60 DAY)").await;
+        let product = insert(&pool, "INSERT INTO products (name, price, in_stock) VALUES ('Rye bread', 3.50, TRUE)").await;
+        let order = insert(&pool, &format!("INSERT INTO orders (customer_id, product_id, quantity, order_date) VALUES ({}, {}, 1, '2025-03-01')", customer, product)).await;
+
+        assert_eq!(purge_table(&pool, "customers", DAY).await.unwrap(), 0);
+        assert!(exists(&pool, "customers", customer).await);
+
+        // Once the order is trashed but not yet due, the customer is tried and skipped
+        sqlx::query("UPDATE orders SET deleted_at = NOW() WHERE id = ?").bind(order).execute(&pool).await.unwrap();
+        assert_eq!(purge_table(&pool, "orders", DAY).await.unwrap(), 0);
+        assert_eq!(purge_table(&pool, "customers", DAY).await.unwrap(), 0);
+        assert!(exists(&pool, "customers", customer).await);
+
+        // After the order is purged the customer follows
+        sqlx::query("UPDATE orders SET deleted_at = NOW() - INTERVAL 2 DAY WHERE id = ?").bind(order).execute(&pool).await.unwrap();
+        assert_eq!(purge_table(&pool, "orders", DAY).await.unwrap(), 1);
+        assert_eq!(purge_table(&pool, "customers", DAY).await.unwrap(), 1);
+        assert!(!exists(&pool, "customers", customer).await);
+    }
+}
@@ -1,12 +1,18 @@
-use axum::{http::HeaderMap, Json, http::StatusCode, response::{IntoResponse, Response}};
+use axum::{http::{header, HeaderMap, HeaderValue, Uri}, Json, http::StatusCode, response::{IntoResponse, Response}};
 use serde::Serialize;
-use serde_json::{json, Value};
-use sqlx::Error as SqlxError;
-use validator::{ValidationError, ValidationErrors};
+use serde_json::{json, Map, Value};
+use sqlx::{error::ErrorKind, mysql::{MySqlQueryResult, MySqlRow}, query_builder::Separated, Encode, Error as SqlxError, Executor, FromRow, MySql, MySqlConnection, MySqlPool, QueryBuilder, Type};
+use std::{borrow::Cow, collections::{BTreeMap, HashSet}};
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
 use rust_decimal::Decimal;
 use thiserror::Error;
-use chrono::NaiveDate;
-use tracing::error;
+use chrono::{DateTime, NaiveDate, Utc};
+use tracing::{error, info};
+use crate::middleware::request_id::current_request_id;
+use crate::metrics::TimedQuery;
+use crate::audit::{self, AuditAction};
+use crate::config::DeletionPolicy;
+use crate::dto::v1::{BlockedId, BulkDeleteReport};
 
 
 // Custom error type for the application
@@ -16,8 +22,16 @@ pub enum AppError {
     DatabaseError(#[from] SqlxError),
     #[error("Validation error: {0}")]
     ValidationError(ValidationErrors),
-    #[error("Resource not found")]
-    NotFound,
+    #[error("No {resource} found with id {id}")]
+    NotFound { resource: &'static str, id: String, fields: Option<ValidationErrors> },
+    #[error("Conflict: {message}")]
+    Conflict { message: String, fields: Option<ValidationErrors> },
+    #[error("The {resource} with id {id} has changed (now at version {version})")]
+    PreconditionFailed { resource: &'static str, id: String, version: i32 },
+    #[error("Idempotency key {key} was already used for a different request")]
+    IdempotencyKeyReused { key: String },
+    #[error("Request body exceeds {limit} bytes")]
+    PayloadTooLarge { limit: usize },
     #[allow(dead_code)]
     #[error("Unauthorized")]
     Unauthorized,
@@ -25,30 +39,597 @@ pub enum AppError {
     InternalServerError,
 }
 
-// Implement `IntoResponse` for `AppError` to convert it into an HTTP response
+impl AppError {
+    pub fn not_found(resource: &'static str, id: impl std::fmt::Display) -> Self {
+        AppError::NotFound { resource, id: id.to_string(), fields: None }
+    }
+
+    // Stable, machine-readable code for each variant
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::DatabaseError(SqlxError::PoolTimedOut | SqlxError::PoolClosed | SqlxError::Io(_)) => "database_unavailable",
+            AppError::DatabaseError(_) => "database_error",
+            AppError::ValidationError(_) => "validation_error",
+            AppError::NotFound { .. } => "not_found",
+            AppError::Conflict { .. } => "conflict",
+            AppError::PreconditionFailed { .. } => "precondition_failed",
+            AppError::IdempotencyKeyReused { .. } => "idempotency_key_reused",
+            AppError::PayloadTooLarge { .. } => "payload_too_large",
+            AppError::Unauthorized => "unauthorized",
+            AppError::InternalServerError => "internal_server_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::DatabaseError(SqlxError::PoolTimedOut | SqlxError::PoolClosed | SqlxError::Io(_)) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::Conflict { .. } => StatusCode::CONFLICT,
+            AppError::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
+            AppError::IdempotencyKeyReused { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+// Implement `IntoResponse` for `AppError` to convert it into an RFC 7807 `application/problem+json` response
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
-            AppError::ValidationError(_) => (StatusCode::BAD_REQUEST, "Validation error"),
-            AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
-            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
-            AppError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
-        };
+        let status = self.status();
+        let code = self.code();
 
-        let body = json!({
-            "error": error_message,
+        if status.is_server_error() {
+            error!(code, error = %self, "Request failed");
+        }
+
+        let mut body = json!({
+            "type": format!("/problems/{}", code.replace('_', "-")),
+            "title": status.canonical_reason().unwrap_or("Error"),
+            "status": status.as_u16(),
+            "code": code,
         });
 
-        (status, Json(body)).into_response()
+        let (detail, fields) = match self {
+            AppError::DatabaseError(_) if status == StatusCode::SERVICE_UNAVAILABLE => ("Database unavailable".to_string(), None),
+            AppError::DatabaseError(_) => ("Database error".to_string(), None),
+            AppError::ValidationError(errors) => ("Validation error".to_string(), Some(errors)),
+            AppError::NotFound { resource, id, fields } => {
+                body["resource"] = json!(resource);
+                body["id"] = json!(id);
+                (format!("No {} found with id {}", resource, id), fields)
+            }
+            AppError::Conflict { message, fields } => (message, fields),
+            AppError::PreconditionFailed { resource, id, version } => {
+                body["resource"] = json!(resource);
+                body["id"] = json!(id);
+                body["etag"] = json!(etag(version));
+                (format!("The {} with id {} has changed (now at version {}); refetch it and retry", resource, id, version), None)
+            }
+            AppError::IdempotencyKeyReused { key } => {
+                body["idempotency_key"] = json!(key);
+                (format!("Idempotency key {} was already used for a different request; send a new key", key), None)
+            }
+            AppError::PayloadTooLarge { limit } => {
+                body["limit"] = json!(limit);
+                (format!("The request body exceeds {} bytes", limit), None)
+            }
+            AppError::Unauthorized => ("Unauthorized".to_string(), None),
+            AppError::InternalServerError => ("Internal server error".to_string(), None),
+        };
+
+        body["detail"] = json!(detail);
+
+        if let Some(request_id) = current_request_id() {
+            body["request_id"] = json!(request_id);
+        }
+
+        // Per-field details let the admin forms highlight the offending inputs
+        if let Some(errors) = fields {
+            body["fields"] = validation_errors_json(&errors);
+        }
+
+        (
+            status,
+            [(header::CONTENT_TYPE, HeaderValue::from_static("application/problem+json"))],
+            Json(body),
+        )
+            .into_response()
     }
 }
 
+// Utility function to render `ValidationErrors` as `{ field: [{ code, message }] }`
+fn validation_errors_json(errors: &ValidationErrors) -> Value {
+    let mut fields = Map::new();
+    for (field, kind) in errors.errors() {
+        let value = match kind {
+            ValidationErrorsKind::Field(errors) => Value::Array(
+                errors
+                    .iter()
+                    .map(|e| json!({
+                        "code": e.code,
+                        "message": e.message.as_ref().unwrap_or(&e.code),
+                    }))
+                    .collect(),
+            ),
+            ValidationErrorsKind::Struct(errors) => validation_errors_json(errors),
+            ValidationErrorsKind::List(items) => Value::Object(
+                items
+                    .iter()
+                    .map(|(index, errors)| (index.to_string(), validation_errors_json(errors)))
+                    .collect(),
+            ),
+        };
+        fields.insert(field.to_string(), value);
+    }
+    Value::Object(fields)
+}
+
+// Utility function to tell a missing row (404) apart from a failing database (500/503)
+pub fn map_fetch_error(e: SqlxError, resource: &'static str, id: impl std::fmt::Display) -> AppError {
+    match e {
+        SqlxError::RowNotFound => AppError::not_found(resource, id),
+        e => {
+            error!(resource, id = %id, error = ?e, "Failed to fetch record");
+            AppError::DatabaseError(e)
+        }
+    }
+}
+
+// Utility function returning the key generated by an INSERT; `id` columns are INT, so it always fits
+pub fn inserted_id(result: &MySqlQueryResult) -> i32 {
+    result.last_insert_id() as i32
+}
+
 // Utility function to generate JSON responses
 pub fn json_response<T: Serialize>(data: T) -> Json<Value> {
     Json(json!({ "data": data }))
 }
 
+// Utility function to generate `201 Created` responses whose `Location` points at the new record
+pub fn created_response<T: Serialize>(collection: &Uri, id: i32, version: i32, data: T) -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut headers = etag_header(version);
+    let location = format!("{}/{}", collection.path().trim_end_matches('/'), id);
+    if let Ok(value) = HeaderValue::from_str(&location) {
+        headers.insert(header::LOCATION, value);
+    }
+    (StatusCode::CREATED, headers, json_response(data))
+}
+
+// Utility function to render a row version as a strong entity tag
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+// Utility function to generate the `ETag` header for a single record
+pub fn etag_header(version: i32) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&etag(version)) {
+        headers.insert(header::ETAG, value);
+    }
+    headers
+}
+
+// Utility function to read `If-Match`; `None` when the client sent no precondition
+pub fn if_match(headers: &HeaderMap) -> Option<Vec<String>> {
+    entity_tags(headers, header::IF_MATCH)
+}
+
+// Utility function to split a list of entity tags (`If-Match`, `If-None-Match`)
+fn entity_tags(headers: &HeaderMap, name: header::HeaderName) -> Option<Vec<String>> {
+    let values: Vec<String> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+// Tables whose rows are soft-deleted: `deleted_at` moves them to the trash until restored or purged
+pub const SOFT_DELETE_TABLES: &[&str] = &["products", "customers", "orders"];
+
+// Live rows that keep a row from being deleted: (table, referencing table, column)
+const REFERENCES: &[(&str, &str, &str)] = &[
+    ("customers", "orders", "customer_id"),
+    ("products", "orders", "product_id"),
+];
+
+// Utility function building the condition that none of a row's references from live rows
+// remain, for queries over `table`
+pub fn unreferenced(table: &str) -> String {
+    REFERENCES
+        .iter()
+        .filter(|(referenced, _, _)| *referenced == table)
+        .map(|(_, dependent, column)| {
+            format!(" AND NOT EXISTS (SELECT 1 FROM {0} WHERE {0}.{1} = {2}.id AND {0}.deleted_at IS NULL)", dependent, column, table)
+        })
+        .collect()
+}
+
+// Utility function to lock a row for writing and enforce `If-Match` against its version
+// (weak tags never match, as RFC 9110 requires strong comparison here); rows in the trash
+// count as missing
+pub async fn lock_version(
+    conn: &mut MySqlConnection,
+    table: &'static str,
+    resource: &'static str,
+    id: i32,
+    if_match: Option<&[String]>,
+) -> Result<i32, AppError> {
+    let (version, deleted) = lock_row(conn, table, resource, id).await?;
+    if deleted {
+        return Err(AppError::not_found(resource, id));
+    }
+    check_if_match(resource, id, version, if_match)?;
+    Ok(version)
+}
+
+// Utility function like `lock_version`, for a row that must be in the trash (restoring it)
+async fn lock_deleted_version(
+    conn: &mut MySqlConnection,
+    table: &'static str,
+    resource: &'static str,
+    id: i32,
+    if_match: Option<&[String]>,
+) -> Result<i32, AppError> {
+    let (version, deleted) = lock_row(conn, table, resource, id).await?;
+    if !deleted {
+        return Err(AppError::Conflict { message: format!("The {} with id {} is not deleted", resource, id), fields: None });
+    }
+    check_if_match(resource, id, version, if_match)?;
+    Ok(version)
+}
+
+async fn lock_row(conn: &mut MySqlConnection, table: &'static str, resource: &'static str, id: i32) -> Result<(i32, bool), AppError> {
+    let deleted = if SOFT_DELETE_TABLES.contains(&table) { "deleted_at IS NOT NULL" } else { "FALSE" };
+    sqlx::query_as(&format!("SELECT version, {} FROM {} WHERE id = ? FOR UPDATE", deleted, table))
+        .bind(id)
+        .fetch_one(conn)
+        .timed("lock_version")
+        .await
+        .map_err(|e| map_fetch_error(e, resource, id))
+}
+
+fn check_if_match(resource: &'static str, id: i32, version: i32, if_match: Option<&[String]>) -> Result<(), AppError> {
+    if let Some(tags) = if_match {
+        let current = etag(version);
+        if !tags.iter().any(|tag| tag == "*" || *tag == current) {
+            return Err(AppError::PreconditionFailed { resource, id: id.to_string(), version });
+        }
+    }
+    Ok(())
+}
+
+// Live rows still referencing a row: the referencing table, its column and the rows' ids
+struct Dependents {
+    table: &'static str,
+    column: &'static str,
+    ids: Vec<i32>,
+}
+
+async fn live_dependents(conn: &mut MySqlConnection, table: &'static str, id: i32) -> Result<Option<Dependents>, AppError> {
+    for &(_, dependent, column) in REFERENCES.iter().filter(|(referenced, _, _)| *referenced == table) {
+        let query = format!("SELECT id FROM {} WHERE {} = ? AND deleted_at IS NULL ORDER BY id FOR UPDATE", dependent, column);
+        let ids: Vec<i32> = sqlx::query_scalar(&query)
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .timed("live_dependents")
+            .await
+            .map_err(AppError::DatabaseError)?;
+        if !ids.is_empty() {
+            return Ok(Some(Dependents { table: dependent, column, ids }));
+        }
+    }
+    Ok(None)
+}
+
+// Utility function moving a row to the trash once it is locked, applying the deletion policy
+// to any live rows that still reference it
+pub async fn soft_delete(
+    conn: &mut MySqlConnection,
+    table: &'static str,
+    resource: &'static str,
+    id: i32,
+    if_match: Option<&[String]>,
+    policy: DeletionPolicy,
+) -> Result<(), AppError> {
+    // Check the row exists and still has the version the client saw
+    lock_version(&mut *conn, table, resource, id, if_match).await?;
+
+    let dependents = live_dependents(&mut *conn, table, id).await?;
+    if let (Some(dependents), DeletionPolicy::Block) = (&dependents, policy) {
+        return Err(referenced_error(resource, dependents.table, &dependents.ids));
+    }
+    release_dependents(&mut *conn, table, id, dependents.as_ref(), policy).await?;
+    mark_deleted(conn, table, id).await
+}
+
+// Apply the deletion policy to a row about to be trashed: cascade to the live rows referencing
+// it, or anonymise it whether or not anything still does
+async fn release_dependents(
+    conn: &mut MySqlConnection,
+    table: &'static str,
+    id: i32,
+    dependents: Option<&Dependents>,
+    policy: DeletionPolicy,
+) -> Result<(), AppError> {
+    match (policy, dependents) {
+        (DeletionPolicy::Block, _) | (DeletionPolicy::Cascade, None) => Ok(()),
+        (DeletionPolicy::Cascade, Some(dependents)) => {
+            let before = audit::snapshots(&mut *conn, dependents.table, &dependents.ids).await?;
+            let query = format!("UPDATE {} SET deleted_at = NOW(), version = version + 1 WHERE {} = ? AND deleted_at IS NULL", dependents.table, dependents.column);
+            sqlx::query(&query)
+                .bind(id)
+                .execute(&mut *conn)
+                .timed("cascade_delete")
+                .await
+                .map_err(AppError::DatabaseError)?;
+            audit::record_all(conn, AuditAction::Delete, dependents.table, before).await?;
+            info!(resource = table, id, dependent = dependents.table, count = dependents.ids.len(), "Cascaded delete to dependent rows");
+            Ok(())
+        }
+        (DeletionPolicy::Anonymise, _) => anonymise(conn, table, id).await,
+    }
+}
+
+// Scrub the personal data of a row being trashed, keeping its orders; only customers carry any
+async fn anonymise(conn: &mut MySqlConnection, table: &'static str, id: i32) -> Result<(), AppError> {
+    if table != "customers" {
+        return Ok(());
+    }
+
+    // The email stays unique, so the address can be used again by a new customer
+    sqlx::query("UPDATE customers SET name = 'Deleted customer', email = CONCAT('deleted-', id, '@invalid'), address = '' WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .timed("anonymise_customer")
+        .await
+        .map_err(AppError::DatabaseError)?;
+
+    let address_ids = owned_ids(&mut *conn, "customer_addresses", id).await?;
+    let addresses = audit::snapshots(&mut *conn, "customer_addresses", &address_ids).await?;
+    sqlx::query("DELETE FROM customer_addresses WHERE customer_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .timed("anonymise_customer")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    audit::record_all(&mut *conn, AuditAction::Delete, "customer_addresses", addresses).await?;
+
+    let order_ids = owned_ids(&mut *conn, "orders", id).await?;
+    let orders = audit::snapshots(&mut *conn, "orders", &order_ids).await?;
+    sqlx::query(
+        "UPDATE orders SET shipping_street = NULL, shipping_postal_code = NULL, billing_street = NULL, billing_postal_code = NULL, \
+         version = version + 1 WHERE customer_id = ?",
+    )
+    .bind(id)
+    .execute(&mut *conn)
+    .timed("anonymise_customer")
+    .await
+    .map_err(AppError::DatabaseError)?;
+    audit::record_all(&mut *conn, AuditAction::Update, "orders", orders).await?;
+
+    // Earlier snapshots would otherwise keep what was just scrubbed
+    audit::redact_customer(conn, id).await?;
+    info!(resource = table, id, "Anonymised customer");
+    Ok(())
+}
+
+// Utility function taking a row back out of the trash
+pub async fn restore(
+    conn: &mut MySqlConnection,
+    table: &'static str,
+    resource: &'static str,
+    id: i32,
+    if_match: Option<&[String]>,
+) -> Result<(), AppError> {
+    lock_deleted_version(&mut *conn, table, resource, id, if_match).await?;
+
+    let before = audit::snapshot(&mut *conn, table, id).await?;
+    sqlx::query(&format!("UPDATE {} SET deleted_at = NULL, version = version + 1 WHERE id = ?", table))
+        .bind(id)
+        .execute(&mut *conn)
+        .timed("restore")
+        .await
+        .map_err(|e| map_constraint_violation(e, resource))?;
+    audit::record(conn, AuditAction::Restore, table, id, before).await
+}
+
+async fn mark_deleted(conn: &mut MySqlConnection, table: &'static str, id: i32) -> Result<(), AppError> {
+    let before = audit::snapshot(&mut *conn, table, id).await?;
+    sqlx::query(&format!("UPDATE {} SET deleted_at = NOW(), version = version + 1 WHERE id = ?", table))
+        .bind(id)
+        .execute(&mut *conn)
+        .timed("soft_delete")
+        .await
+        .map_err(AppError::DatabaseError)?;
+    audit::record(conn, AuditAction::Delete, table, id, before).await
+}
+
+// Utility function listing a customer's rows in a table with a `customer_id` column
+pub async fn owned_ids(conn: &mut MySqlConnection, table: &'static str, customer_id: i32) -> Result<Vec<i32>, AppError> {
+    sqlx::query_scalar(&format!("SELECT id FROM {} WHERE customer_id = ? ORDER BY id", table))
+        .bind(customer_id)
+        .fetch_all(conn)
+        .timed("owned_ids")
+        .await
+        .map_err(AppError::DatabaseError)
+}
+
+// The 409 raised when live rows still reference the row being deleted
+fn referenced_error(resource: &str, dependent: &str, ids: &[i32]) -> AppError {
+    let dependents = describe_dependents(dependent, ids);
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        "id",
+        ValidationError::new("referenced").with_message(Cow::from(format!("Still referenced by {}", dependents))),
+    );
+
+    AppError::Conflict {
+        message: format!("The {} is still referenced by {}", resource, dependents),
+        fields: Some(errors),
+    }
+}
+
+// "orders 3, 8 and 12 more" for error messages; the table name alone when the ids are unknown
+fn describe_dependents(dependent: &str, ids: &[i32]) -> String {
+    const LISTED: usize = 10;
+    if ids.is_empty() {
+        return dependent.to_string();
+    }
+    let listed: Vec<String> = ids.iter().take(LISTED).map(i32::to_string).collect();
+    match ids.len().checked_sub(LISTED).filter(|more| *more > 0) {
+        Some(more) => format!("{} {} and {} more", dependent, listed.join(", "), more),
+        None => format!("{} {}", dependent, listed.join(", ")),
+    }
+}
+
+// Utility function to render a timestamp as an HTTP-date (`Last-Modified`, `If-Modified-Since`)
+pub fn http_date(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+// Utility function evaluating `If-None-Match`, or failing that `If-Modified-Since`, for a read
+// (RFC 9110 section 13.2.2: weak comparison, and the entity tag takes precedence)
+pub fn is_not_modified(headers: &HeaderMap, current: &str, last_modified: Option<DateTime<Utc>>) -> bool {
+    if let Some(tags) = entity_tags(headers, header::IF_NONE_MATCH) {
+        let opaque = |tag: &str| tag.trim_start_matches("W/").to_string();
+        return tags.iter().any(|tag| tag == "*" || opaque(tag) == opaque(current));
+    }
+
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+    match (last_modified, since) {
+        // HTTP-dates have whole-second precision
+        (Some(modified), Some(since)) => modified.timestamp() <= since.timestamp(),
+        _ => false,
+    }
+}
+
+// Utility function for cacheable reads: `304 Not Modified` without a body when the client's
+// copy is current, otherwise `200` with the body built by `data`
+pub fn conditional_response<T: Serialize>(
+    request: &HeaderMap,
+    mut headers: HeaderMap,
+    current: &str,
+    last_modified: Option<DateTime<Utc>>,
+    cache_control: HeaderValue,
+    data: impl FnOnce() -> T,
+) -> Response {
+    if let Ok(value) = HeaderValue::from_str(current) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Some(value) = last_modified.and_then(|modified| HeaderValue::from_str(&http_date(modified)).ok()) {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+    headers.insert(header::CACHE_CONTROL, cache_control);
+
+    if is_not_modified(request, current, last_modified) {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+    (headers, json_response(data())).into_response()
+}
+
+// Largest batch the bulk endpoints accept
+pub const MAX_BULK_ITEMS: usize = 1000;
+
+// Validation function for bulk request bodies: rejects empty or oversized batches and runs every
+// item's validators, reporting failures as `items.<index>.<field>`
+pub fn validate_items<'a, T: Validate + 'a>(items: impl ExactSizeIterator<Item = &'a T>) -> Result<(), AppError> {
+    let count = items.len();
+    if count == 0 || count > MAX_BULK_ITEMS {
+        let message = if count == 0 { "No items provided".to_string() } else { format!("At most {} items per request", MAX_BULK_ITEMS) };
+        let mut errors = ValidationErrors::new();
+        errors.add("items", ValidationError::new("length").with_message(Cow::from(message)));
+        return Err(AppError::ValidationError(errors));
+    }
+
+    let failed: BTreeMap<usize, Box<ValidationErrors>> = items
+        .enumerate()
+        .filter_map(|(index, item)| item.validate().err().map(|errors| (index, Box::new(errors))))
+        .collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::ValidationError(item_errors(failed)))
+    }
+}
+
+// Validation function rejecting a bulk update that lists the same id twice
+pub fn validate_unique_ids(ids: impl Iterator<Item = i32>) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    let duplicates: BTreeMap<usize, Box<ValidationErrors>> = ids
+        .enumerate()
+        .filter(|(_, id)| !seen.insert(*id))
+        .map(|(index, _)| {
+            let mut errors = ValidationErrors::new();
+            errors.add("id", ValidationError::new("unique").with_message(Cow::from("Id already appears earlier in the request")));
+            (index, Box::new(errors))
+        })
+        .collect();
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::ValidationError(item_errors(duplicates)))
+    }
+}
+
+// Utility function to attribute an error raised while writing one item of a bulk request to its index
+pub fn bulk_item_error(index: usize, e: AppError) -> AppError {
+    let nest = |errors: ValidationErrors| item_errors(BTreeMap::from([(index, Box::new(errors))]));
+    match e {
+        AppError::ValidationError(errors) => AppError::ValidationError(nest(errors)),
+        AppError::Conflict { message, fields } => AppError::Conflict {
+            message: format!("Item {}: {}", index, message),
+            fields: fields.map(nest),
+        },
+        e => e,
+    }
+}
+
+fn item_errors(items: BTreeMap<usize, Box<ValidationErrors>>) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.errors_mut().insert(Cow::Borrowed("items"), ValidationErrorsKind::List(items));
+    errors
+}
+
+// Utility function to load the rows written by a bulk request, in request order
+pub async fn fetch_by_ids<T>(pool: &MySqlPool, table: &'static str, ids: &[i32]) -> Result<Vec<T>, AppError>
+where
+    T: for<'r> FromRow<'r, MySqlRow> + Send + Unpin,
+{
+    let mut query = QueryBuilder::<MySql>::new(format!("SELECT * FROM {} WHERE id IN (", table));
+    let mut in_list = query.separated(", ");
+    for id in ids {
+        in_list.push_bind(id);
+    }
+    query.push(") ORDER BY FIELD(id");
+    for id in ids {
+        query.push(", ").push_bind(id);
+    }
+    query.push(")");
+
+    query
+        .build_query_as::<T>()
+        .fetch_all(pool)
+        .timed("fetch_by_ids")
+        .await
+        .map_err(AppError::DatabaseError)
+}
+
 // Utility function to generate `Content-Range` headers
 pub fn content_range_header(resource: &str, total: usize) -> HeaderMap {
     let mut headers = HeaderMap::new();
@@ -61,48 +642,470 @@ pub fn content_range_header(resource: &str, total: usize) -> HeaderMap {
     headers
 }
 
+// Utility function for the `Content-Range` header of one page of a longer list
+pub fn content_range_page_header(resource: &str, offset: u64, count: usize, total: u64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let content_range = if count == 0 {
+        format!("{} */{}", resource, total)
+    } else {
+        format!("{} {}-{}/{}", resource, offset, offset + count as u64 - 1, total)
+    };
+    headers.insert("Content-Range", content_range.parse().unwrap());
+    headers
+}
+
 // Validation function for Decimal range
 pub fn validate_decimal_range(value: &Decimal) -> Result<(), ValidationError> {
     let min = Decimal::new(0, 0);
     if *value < min {
-        return Err(ValidationError::new("Value must be greater than or equal to the minimum"));
+        return Err(ValidationError::new("range").with_message(Cow::from("Value must be greater than or equal to the minimum")));
     }
     Ok(())
 }
 
 // Validation function to check if a customer exists
-pub async fn validate_customer_exists(pool: &sqlx::MySqlPool, customer_id: i32) -> Result<(), AppError> {
-    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM customers WHERE id = ?)")
+pub async fn validate_customer_exists<'e>(executor: impl Executor<'e, Database = MySql>, customer_id: i32) -> Result<(), AppError> {
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM customers WHERE id = ? AND deleted_at IS NULL)")
         .bind(customer_id)
-        .fetch_one(pool)
+        .fetch_one(executor)
+        .timed("validate_customer_exists")
         .await
         .map_err(AppError::DatabaseError)?;
 
     if !exists {
-        return Err(AppError::NotFound);
+        return Err(AppError::not_found("customer", customer_id));
     }
     Ok(())
 }
 
 // Validation function to check if a product exists
-pub async fn validate_product_exists(pool: &sqlx::MySqlPool, product_id: i32) -> Result<(), AppError> {
-    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM products WHERE id = ?)")
+pub async fn validate_product_exists<'e>(executor: impl Executor<'e, Database = MySql>, product_id: i32) -> Result<(), AppError> {
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM products WHERE id = ? AND deleted_at IS NULL)")
         .bind(product_id)
-        .fetch_one(pool)
+        .fetch_one(executor)
+        .timed("validate_product_exists")
         .await
         .map_err(AppError::DatabaseError)?;
 
     if !exists {
-        return Err(AppError::NotFound);
+        return Err(AppError::not_found("product", product_id));
     }
     Ok(())
 }
 
+// Unique indexes and the field they guard
+const UNIQUE_CONSTRAINT_FIELDS: &[(&str, &str)] = &[
+    ("customers_email_unique", "email"),
+];
+
+// Utility function to map database constraint violations (unique, foreign key) to field-level errors
+pub fn map_constraint_violation(e: SqlxError, resource: &str) -> AppError {
+    let (kind, message) = match e.as_database_error() {
+        Some(db_error) => (db_error.kind(), db_error.message().to_string()),
+        None => return AppError::DatabaseError(e),
+    };
+
+    match kind {
+        ErrorKind::UniqueViolation => {
+            // e.g. "Duplicate entry 'a@b.c' for key 'customers_email_unique'"
+            let field = UNIQUE_CONSTRAINT_FIELDS
+                .iter()
+                .find(|(key, _)| message.contains(key))
+                .map(|(_, field)| *field);
+
+            let fields = field.map(|field| {
+                let mut errors = ValidationErrors::new();
+                errors.add(field, ValidationError::new("unique").with_message(Cow::from("Value is already in use")));
+                errors
+            });
+
+            AppError::Conflict {
+                message: match field {
+                    Some(field) => format!("A {} with this {} already exists", resource, field),
+                    None => format!("A {} with these values already exists", resource),
+                },
+                fields,
+            }
+        }
+        ErrorKind::ForeignKeyViolation if message.contains("parent row") => {
+            referenced_error(resource, referencing_table(&message).unwrap_or("other records"), &[])
+        }
+        ErrorKind::ForeignKeyViolation => {
+            // e.g. "... FOREIGN KEY (`customer_id`) REFERENCES `customers` (`id`))"
+            let field = message
+                .split("FOREIGN KEY (`")
+                .nth(1)
+                .and_then(|rest| rest.split('`').next())
+                .unwrap_or("id")
+                .to_string();
+
+            let mut errors = ValidationErrors::new();
+            errors.errors_mut().insert(
+                Cow::Owned(field),
+                ValidationErrorsKind::Field(vec![
+                    ValidationError::new("foreign_key").with_message(Cow::from("Referenced record does not exist")),
+                ]),
+            );
+
+            AppError::ValidationError(errors)
+        }
+        _ => AppError::DatabaseError(e),
+    }
+}
+
+// Utility function naming the table whose rows block a delete, from a foreign key error message
+// e.g. "Cannot delete or update a parent row: ... (`db`.`orders`, CONSTRAINT ..."
+fn referencing_table(message: &str) -> Option<&str> {
+    message.split('`').nth(3)
+}
+
+// Utility function to tell which table still references a row, if that is why a delete failed
+pub fn blocking_table(e: &SqlxError) -> Option<String> {
+    let db_error = e.as_database_error()?;
+    let message = db_error.message();
+    if db_error.kind() == ErrorKind::ForeignKeyViolation && message.contains("parent row") {
+        Some(referencing_table(message).unwrap_or("other records").to_string())
+    } else {
+        None
+    }
+}
+
+// Utility function for the bulk-delete endpoints: moves each id to the trash and reports which
+// were deleted, missing or, under the `Block` policy, still referenced by live rows. With
+// `atomic`, any missing or referenced id rolls the whole batch back instead
+pub async fn delete_by_ids(
+    pool: &MySqlPool,
+    table: &'static str,
+    resource: &'static str,
+    ids: &[i32],
+    atomic: bool,
+    policy: DeletionPolicy,
+) -> Result<BulkDeleteReport, AppError> {
+    if ids.is_empty() || ids.len() > MAX_BULK_ITEMS {
+        let message = if ids.is_empty() { "No IDs provided".to_string() } else { format!("At most {} IDs per request", MAX_BULK_ITEMS) };
+        let mut errors = ValidationErrors::new();
+        errors.add("ids", ValidationError::new("required").with_message(Cow::from(message)));
+        return Err(AppError::ValidationError(errors));
+    }
+
+    let mut seen = HashSet::new();
+    let mut report = BulkDeleteReport::default();
+    let mut tx = pool.begin().await.map_err(AppError::DatabaseError)?;
+
+    for &id in ids.iter().filter(|id| seen.insert(**id)) {
+        match lock_version(&mut tx, table, resource, id, None).await {
+            Ok(_) => {}
+            Err(AppError::NotFound { .. }) => {
+                report.missing.push(id);
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+        match live_dependents(&mut tx, table, id).await? {
+            Some(dependents) if policy == DeletionPolicy::Block => {
+                report.blocked.push(BlockedId { id, referenced_by: dependents.table.to_string(), dependents: dependents.ids });
+            }
+            dependents => {
+                release_dependents(&mut tx, table, id, dependents.as_ref(), policy).await?;
+                mark_deleted(&mut tx, table, id).await?;
+                report.deleted.push(id);
+            }
+        }
+    }
+
+    if atomic && !report.blocked.is_empty() {
+        let dependents: Vec<String> = report.blocked.iter().map(|blocked| format!("{} ({})", blocked.id, blocked.referenced_by)).collect();
+        return Err(AppError::Conflict {
+            message: format!("Nothing was deleted: {} {} still referenced", resource, dependents.join(", ")),
+            fields: Some(delete_errors(ids, &report)),
+        });
+    }
+    if atomic && !report.missing.is_empty() {
+        let missing: Vec<String> = report.missing.iter().map(|id| id.to_string()).collect();
+        return Err(AppError::NotFound { resource, id: missing.join(", "), fields: Some(delete_errors(ids, &report)) });
+    }
+
+    tx.commit().await.map_err(AppError::DatabaseError)?;
+    Ok(report)
+}
+
+// Per-index errors for an all-or-nothing bulk delete that was rolled back
+fn delete_errors(ids: &[i32], report: &BulkDeleteReport) -> ValidationErrors {
+    let items = ids
+        .iter()
+        .enumerate()
+        .filter_map(|(index, id)| {
+            let error = if report.missing.contains(id) {
+                ValidationError::new("not_found").with_message(Cow::from("No record with this id"))
+            } else {
+                let blocked = report.blocked.iter().find(|blocked| blocked.id == *id)?;
+                ValidationError::new("referenced").with_message(Cow::from(format!("Still referenced by {}", describe_dependents(&blocked.referenced_by, &blocked.dependents))))
+            };
+            let mut errors = ValidationErrors::new();
+            errors.add("id", error);
+            Some((index, Box::new(errors)))
+        })
+        .collect();
+    item_errors(items)
+}
+
 // Validation function for dates (e.g., order date must be after 2020-01-01)
 pub fn validate_date(date: &NaiveDate) -> Result<(), ValidationError> {
     let min_date = NaiveDate::from_ymd_opt(2020, 1, 1).expect("Invalid date");
     if *date < min_date {
-        return Err(ValidationError::new("Date must be after 2020-01-01"));
+        return Err(ValidationError::new("date").with_message(Cow::from("Date must be after 2020-01-01")));
+    }
+    Ok(())
+}
+
+// Validation function for address types (billing or shipping)
+pub fn validate_address_type(address_type: &str) -> Result<(), ValidationError> {
+    if address_type != "billing" && address_type != "shipping" {
+        return Err(ValidationError::new("address_type").with_message(Cow::from("Address type must be either billing or shipping")));
     }
     Ok(())
 }
+
+// Validation function for ISO 3166-1 alpha-2 country codes (e.g., SK, DE)
+pub fn validate_country_code(country_code: &str) -> Result<(), ValidationError> {
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(ValidationError::new("country_code").with_message(Cow::from("Country code must be a two-letter ISO code")));
+    }
+    Ok(())
+}
+
+// Utility function for JSON Merge Patch fields: absent stays `None`, `null` becomes `Some(None)`
+pub fn patch_field<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
+// Validation function for merge patches: run the field validators on the supplied values and
+// reject `null` for NOT NULL columns, which can be changed but not removed
+pub fn validate_patch<P: Validate>(patch: &P, cleared_required: &[&'static str]) -> Result<(), AppError> {
+    let mut errors = patch.validate().err().unwrap_or_default();
+    for &field in cleared_required {
+        errors.add(field, ValidationError::new("required").with_message(Cow::from(format!("{} cannot be null", field))));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::ValidationError(errors))
+    }
+}
+
+// Utility function telling whether a merge patch sets a field to `null`
+pub fn is_cleared<T>(field: &Option<Option<T>>) -> bool {
+    matches!(field, Some(None))
+}
+
+// Utility function appending `column = ?` to a dynamic UPDATE when a merge patch supplies the field;
+// returns whether anything was appended
+pub fn push_patch_field<'args, T>(
+    set: &mut Separated<'_, 'args, MySql, &'static str>,
+    column: &str,
+    value: Option<Option<T>>,
+) -> bool
+where
+    T: 'args + Encode<'args, MySql> + Type<MySql> + Send,
+{
+    match value {
+        Some(value) => {
+            set.push(format_args!("{} = ", column)).push_bind_unseparated(value);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::QueryBuilder;
+    use crate::models::product::{CreateProduct, PatchProduct};
+
+    fn patch(body: Value) -> PatchProduct {
+        serde_json::from_value(body).expect("valid patch body")
+    }
+
+    fn error_fields(e: AppError) -> Vec<String> {
+        match e {
+            AppError::ValidationError(errors) => errors.errors().keys().map(|field| field.to_string()).collect(),
+            e => panic!("expected a validation error, got {:?}", e),
+        }
+    }
+
+    fn product(name: &str, price: &str) -> CreateProduct {
+        CreateProduct { name: name.to_string(), description: None, price: price.parse().unwrap(), in_stock: true }
+    }
+
+    // Indexes reported under `items` by a bulk validation error
+    fn failed_items(e: AppError) -> Vec<usize> {
+        let AppError::ValidationError(errors) = e else {
+            panic!("expected a validation error, got {:?}", e);
+        };
+        match errors.errors().get("items") {
+            Some(ValidationErrorsKind::List(items)) => items.keys().copied().collect(),
+            other => panic!("expected per-item errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn merge_patch_tells_omitted_fields_from_null() {
+        let patch = patch(json!({ "name": "Rye bread", "description": null }));
+        assert_eq!(patch.name, Some(Some("Rye bread".to_string())));
+        assert_eq!(patch.description, Some(None));
+        assert_eq!(patch.price, None);
+        assert_eq!(patch.in_stock, None);
+    }
+
+    #[test]
+    fn merge_patch_rejects_null_for_required_fields() {
+        let patch = patch(json!({ "name": null, "description": null, "in_stock": null }));
+        assert_eq!(patch.cleared_required(), vec!["name", "in_stock"]);
+
+        let mut fields = error_fields(validate_patch(&patch, &patch.cleared_required()).unwrap_err());
+        fields.sort();
+        assert_eq!(fields, vec!["in_stock", "name"]);
+    }
+
+    #[test]
+    fn merge_patch_validates_only_supplied_fields() {
+        let empty = patch(json!({}));
+        assert!(validate_patch(&empty, &empty.cleared_required()).is_ok());
+
+        let negative = patch(json!({ "price": "-1.00" }));
+        assert_eq!(error_fields(validate_patch(&negative, &negative.cleared_required()).unwrap_err()), vec!["price"]);
+    }
+
+    #[test]
+    fn merge_patch_updates_only_supplied_columns() {
+        let patch = patch(json!({ "description": null, "in_stock": true }));
+        let mut query = QueryBuilder::<MySql>::new("UPDATE products SET ");
+        let mut set = query.separated(", ");
+        let changed = [
+            push_patch_field(&mut set, "name", patch.name),
+            push_patch_field(&mut set, "description", patch.description),
+            push_patch_field(&mut set, "in_stock", patch.in_stock),
+        ];
+        assert_eq!(changed, [false, true, true]);
+        assert_eq!(query.sql(), "UPDATE products SET description = ?, in_stock = ?");
+    }
+
+    #[test]
+    fn if_match_splits_listed_tags() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(if_match(&headers), None);
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"2\", W/\"3\" ,"));
+        headers.append(header::IF_MATCH, HeaderValue::from_static("\"4\""));
+        assert_eq!(if_match(&headers), Some(vec!["\"2\"".to_string(), "W/\"3\"".to_string(), "\"4\"".to_string()]));
+    }
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn if_match_accepts_the_current_version_or_any() {
+        assert!(check_if_match("order", 1, 4, None).is_ok());
+        assert!(check_if_match("order", 1, 4, Some(&tags(&["\"4\""]))).is_ok());
+        assert!(check_if_match("order", 1, 4, Some(&tags(&["\"2\"", "\"4\""]))).is_ok());
+        assert!(check_if_match("order", 1, 4, Some(&tags(&["*"]))).is_ok());
+    }
+
+    #[test]
+    fn if_match_rejects_a_stale_version() {
+        match check_if_match("order", 1, 4, Some(&tags(&["\"3\""]))) {
+            Err(AppError::PreconditionFailed { resource, id, version }) => {
+                assert_eq!((resource, id.as_str(), version), ("order", "1", 4));
+            }
+            other => panic!("expected 412, got {:?}", other),
+        }
+        // A weak tag never matches strongly
+        assert!(check_if_match("order", 1, 4, Some(&tags(&["W/\"4\""]))).is_err());
+    }
+
+    fn request(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn not_modified_compares_entity_tags_weakly() {
+        assert!(is_not_modified(&request(header::IF_NONE_MATCH, "\"7\""), "\"7\"", None));
+        assert!(is_not_modified(&request(header::IF_NONE_MATCH, "W/\"7\""), "\"7\"", None));
+        assert!(is_not_modified(&request(header::IF_NONE_MATCH, "\"6\", W/\"7\""), "W/\"7\"", None));
+        assert!(is_not_modified(&request(header::IF_NONE_MATCH, "*"), "\"7\"", None));
+        assert!(!is_not_modified(&request(header::IF_NONE_MATCH, "\"6\""), "\"7\"", None));
+        assert!(!is_not_modified(&HeaderMap::new(), "\"7\"", None));
+    }
+
+    #[test]
+    fn not_modified_falls_back_to_the_modification_date() {
+        let modified = DateTime::parse_from_rfc3339("2025-03-20T09:30:15.250Z").unwrap().with_timezone(&Utc);
+        let since = |date: &str| request(header::IF_MODIFIED_SINCE, date);
+
+        assert!(is_not_modified(&since("Thu, 20 Mar 2025 09:30:15 GMT"), "\"7\"", Some(modified)));
+        assert!(!is_not_modified(&since("Thu, 20 Mar 2025 09:30:14 GMT"), "\"7\"", Some(modified)));
+        assert!(!is_not_modified(&since("not a date"), "\"7\"", Some(modified)));
+        assert!(!is_not_modified(&since("Thu, 20 Mar 2025 09:30:15 GMT"), "\"7\"", None));
+
+        // If-None-Match takes precedence over the date
+        let mut headers = since("Thu, 20 Mar 2025 09:30:15 GMT");
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"6\""));
+        assert!(!is_not_modified(&headers, "\"7\"", Some(modified)));
+    }
+
+    #[test]
+    fn bulk_validation_reports_every_failing_item_by_index() {
+        let items = [product("Rye bread", "3.50"), product("", "1.00"), product("Honey", "-2.00")];
+        assert_eq!(failed_items(validate_items(items.iter()).unwrap_err()), vec![1, 2]);
+        assert!(validate_items(items[..1].iter()).is_ok());
+    }
+
+    #[test]
+    fn bulk_validation_rejects_empty_and_oversized_batches() {
+        let none: [CreateProduct; 0] = [];
+        assert_eq!(error_fields(validate_items(none.iter()).unwrap_err()), vec!["items"]);
+
+        let too_many: Vec<CreateProduct> = (0..=MAX_BULK_ITEMS).map(|_| product("Rye bread", "3.50")).collect();
+        assert_eq!(error_fields(validate_items(too_many.iter()).unwrap_err()), vec!["items"]);
+    }
+
+    #[test]
+    fn bulk_update_rejects_repeated_ids() {
+        assert!(validate_unique_ids([1, 2, 3].into_iter()).is_ok());
+        assert_eq!(failed_items(validate_unique_ids([4, 7, 4, 7, 4].into_iter()).unwrap_err()), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn bulk_write_errors_name_the_failing_item() {
+        let mut fields = ValidationErrors::new();
+        fields.add("email", ValidationError::new("unique"));
+        let conflict = AppError::Conflict { message: "A customer with this email already exists".to_string(), fields: Some(fields) };
+
+        match bulk_item_error(3, conflict) {
+            AppError::Conflict { message, fields } => {
+                assert_eq!(message, "Item 3: A customer with this email already exists");
+                assert_eq!(failed_items(AppError::ValidationError(fields.unwrap())), vec![3]);
+            }
+            e => panic!("expected a conflict, got {:?}", e),
+        }
+        assert!(matches!(bulk_item_error(3, AppError::not_found("customer", 9)), AppError::NotFound { .. }));
+    }
+
+    #[test]
+    fn dependents_are_listed_up_to_ten_ids() {
+        assert_eq!(describe_dependents("orders", &[3, 8]), "orders 3, 8");
+        assert_eq!(describe_dependents("orders", &(1..=10).collect::<Vec<_>>()), "orders 1, 2, 3, 4, 5, 6, 7, 8, 9, 10");
+        assert_eq!(describe_dependents("orders", &(1..=13).collect::<Vec<_>>()), "orders 1, 2, 3, 4, 5, 6, 7, 8, 9, 10 and 3 more");
+        // Constraint errors do not say which rows are in the way
+        assert_eq!(describe_dependents("orders", &[]), "orders");
+    }
+}
@@ -1,9 +1,15 @@
 import { fetchUtils } from 'react-admin';
 import { stringify } from 'query-string';
 
-const apiUrl = 'http://localhost:3000/api';
+const apiUrl = 'http://localhost:3000/api/v1';
 const httpClient = fetchUtils.fetchJson;
 
+// Send the version the user was looking at, so concurrent edits fail with 412 instead of being overwritten
+const ifMatch = (previousData) =>
+    previousData && previousData.version !== undefined
+        ? new Headers({ 'If-Match': `"${previousData.version}"` })
+        : undefined;
+
 const dataProvider = {
     getList: async (resource, params) => {
     const { page, perPage } = params.pagination;
@@ -74,6 +80,7 @@ const dataProvider = {
         const { json } = await httpClient(url, {
             method: 'PUT',
             body: JSON.stringify(params.data),
+            headers: ifMatch(params.previousData),
         });
         return {
             data: json.data,
@@ -101,7 +108,7 @@ const dataProvider = {
             });
             console.log("Backend response:", json); // Log the backend response
             return {
-                data: { ...params.data, id: json.id },
+                data: json.data,
             };
         } catch (error) {
             console.error("Error creating resource:", error); // Log any errors
@@ -112,6 +119,7 @@ const dataProvider = {
         const url = `${apiUrl}/${resource}/${params.id}`;
         const { json } = await httpClient(url, {
             method: 'DELETE',
+            headers: ifMatch(params.previousData),
         });
         return {
             data: json.data,
@@ -119,7 +127,8 @@ const dataProvider = {
     },
     deleteMany: async (resource, params) => {
         //const url = `${apiUrl}/${resource}`;
-        const url = `${apiUrl}/${resource}/bulk-delete`;
+        // All-or-nothing: referenced or missing ids fail the whole batch with an error
+        const url = `${apiUrl}/${resource}/bulk-delete?atomic=true`;
 
         console.log("Deleting multiple records from:", url); // Log the URL
         console.log("IDs to delete:", params.ids); // Log the IDs to delete
@@ -132,7 +141,7 @@ const dataProvider = {
             });
             console.log("Backend response for deleteMany:", json); // Log the backend response
             return {
-                data: json.data || params.ids, // Ensure the response includes the deleted IDs
+                data: json.data.deleted, // The backend reports deleted, missing and blocked ids
             };
         } catch (error) {
             console.error("Error deleting multiple records:", error); // Log any errors